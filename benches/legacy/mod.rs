@@ -0,0 +1,73 @@
+//! The char-by-char lexer replaced by [Lexer](dhcpd_parser::lex::Lexer), kept as the baseline
+//! of the benchmarks. It builds a `String` for each word and a keyword error for each word that
+//! is not a keyword, and does not handle the quoted strings with spaces.
+use std::iter::Peekable;
+
+use dhcpd_parser::leases::LeaseKeyword;
+use dhcpd_parser::lex::LexItem;
+use dhcpd_parser::parser::ConfigKeyword;
+
+pub fn lex<S>(input: S) -> Result<Vec<LexItem>, String>
+where
+    S: Into<String>,
+{
+    let mut result = Vec::new();
+    let input_str = input.into();
+    let mut it = input_str.chars().peekable();
+
+    while let Some(&c) = it.peek() {
+        match c {
+            '(' | ')' | '[' | ']' | '{' | '}' => {
+                result.push(LexItem::Paren(c));
+                it.next();
+            }
+            '#' => {
+                consume_comment(&mut it);
+            }
+            ' ' | '\n' | '\t' => {
+                it.next();
+            }
+            ';' => {
+                result.push(LexItem::Endl);
+                it.next();
+            }
+            _ => {
+                let w = get_word(&mut it);
+
+                // Is this the keyword "lease"?
+                if let Ok(kw) = ConfigKeyword::from(&w) {
+                    result.push(LexItem::Decl(kw));
+                } else if let Ok(kw) = LeaseKeyword::from(&w) {
+                    // One of the other valid `LeaseKeyword` words
+                    result.push(LexItem::Opt(kw));
+                } else {
+                    result.push(LexItem::Word(w));
+                }
+            }
+        }
+    }
+    Ok(result)
+}
+
+/// Get the next word up to either whitespace or a line terminator ';"
+fn get_word<T: Iterator<Item = char>>(iter: &mut Peekable<T>) -> String {
+    let mut word = String::new();
+
+    while let Some(&nc) = iter.peek() {
+        if nc.is_whitespace() || nc == ';' {
+            break;
+        }
+
+        word.push(nc);
+        iter.next();
+    }
+    word
+}
+
+/// Advance iterator past comment. The iterator will be sitting either on a '\n' or EOF.
+fn consume_comment<T: Iterator<Item = char>>(iter: &mut Peekable<T>) {
+    // Advance past the '#' we peeked at
+    iter.next();
+
+    while iter.next_if(|&k| k != '\n').is_some() {}
+}
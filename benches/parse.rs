@@ -0,0 +1,64 @@
+//! Throughput of the legacy char-by-char lexer versus the zero-copy [Lexer], and of the owned
+//! [parser::parse] versus the zero-copy [parser::parse_ref] (and [parser::parse_parallel] with
+//! the "rayon" feature).
+//!
+//! The leases file is generated in memory. The number of leases defaults to one million and can
+//! be changed with the `DHCPD_BENCH_LEASES` environment variable.
+use std::fmt::Write;
+
+mod legacy;
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use dhcpd_parser::lex::{lex, Lexer};
+use dhcpd_parser::parser;
+
+/// Generate the contents of an ISC-style leases file with `count` leases.
+fn generate_leases(count: usize) -> String {
+    let mut s = String::from("authoring-byte-order little-endian;\n\n");
+
+    for i in 0..count {
+        let (a, b, c) = ((i >> 16) & 0xff, (i >> 8) & 0xff, i & 0xff);
+        write!(
+            s,
+            "lease 10.{a}.{b}.{c} {{
+  starts 2 2021/11/30 19:57:47;
+  ends 2 2021/11/30 21:57:47;
+  tstp 2 2021/11/30 21:57:47;
+  cltt 2 2021/11/30 19:57:47;
+  binding state free;
+  hardware ethernet 00:ab:{a:02x}:{b:02x}:{c:02x}:10;
+  uid \"\\001\\000\\352\\324A!\\020\";
+  client-hostname \"host-{i}\";
+}}
+",
+        )
+        .unwrap();
+    }
+
+    s
+}
+
+fn bench_parse(c: &mut Criterion) {
+    let count = std::env::var("DHCPD_BENCH_LEASES")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(1_000_000);
+    let content = generate_leases(count);
+
+    let mut group = c.benchmark_group(format!("{}-leases", count));
+    group.sample_size(10);
+    group.throughput(Throughput::Bytes(content.len() as u64));
+
+    group.bench_function("lex_legacy", |b| b.iter(|| legacy::lex(content.as_str()).unwrap().len()));
+    group.bench_function("lex", |b| b.iter(|| lex(content.as_str()).unwrap().len()));
+    group.bench_function("lex_zero_copy", |b| b.iter(|| Lexer::new(&content).count()));
+    group.bench_function("parse", |b| b.iter(|| parser::parse(content.as_str()).unwrap()));
+    group.bench_function("parse_ref", |b| b.iter(|| parser::parse_ref(&content).unwrap().len()));
+    #[cfg(feature = "rayon")]
+    group.bench_function("parse_parallel", |b| b.iter(|| parser::parse_parallel(&content).unwrap()));
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_parse);
+criterion_main!(benches);
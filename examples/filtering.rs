@@ -5,8 +5,6 @@
 extern crate dhcpd_parser;
 
 use crate::dhcpd_parser::parser;
-use std::fs::File;
-use std::io::Read;
 use std::path::PathBuf;
 use std::str::FromStr;
 use chrono::{TimeZone, Utc};
@@ -26,33 +24,9 @@ impl Divider {
     }
 }
 
-/// Load the contents of the leases file
-pub fn load_file(filename: &PathBuf) -> Result<String, String> {
-    return match File::open(&filename) {
-        Ok(mut f) => {
-            let mut s = String::new();
-            f.read_to_string(&mut s).unwrap();
-            Result::Ok(s)
-        },
-        Err(e) => {
-            Result::Err(format!("Failed to open: {}. Error: {}", filename.display(), e))
-        }
-    }
-}
-
 /// Load the leases from the leases file
 pub fn load_leases(filename: &PathBuf) -> Result<Leases, String> {
-    if let Result::Ok(content) = load_file(filename) {
-        return match parser::parse(content) {
-            Result::Ok(res) => {
-                Result::Ok(res.leases)
-            },
-            Result::Err(e) => Result::Err(e)
-        }
-    }
-    else {
-        Result::Err("Failed to load leases".to_string())
-    }
+    parser::parse_file(filename).map(|res| res.leases)
 }
 
 /// List the loaded leases
@@ -0,0 +1,453 @@
+//! Command-line tool to query the contents of a "dhcpd.leases" file (requires the "cli" feature).
+//!
+//! Run `dhcpd-leases --help` for the list of the subcommands. All of the subcommands use the
+//! [parser] to load the file and the [LeaseFilterBuilder] to select the leases. The "effective"
+//! leases are the last entry for each IP in the file (see [LeaseFilterBuilder::effective]).
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fs;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{IpAddr, TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+use clap::{Parser, Subcommand, ValueEnum};
+use dhcpd_parser::common::IpRange;
+use dhcpd_parser::export::{self, Column, DEFAULT_COLUMNS};
+use dhcpd_parser::leases::{Lease, Leases, LeasesMethods};
+use dhcpd_parser::metrics::MetricsBuilder;
+use dhcpd_parser::parser;
+use dhcpd_parser::util::LeaseFilterBuilder;
+
+#[derive(Parser)]
+#[command(name = "dhcpd-leases", version, about = "Query the contents of a dhcpd.leases file")]
+struct Cli {
+    /// The leases file
+    #[arg(short, long, global = true, default_value = "/var/lib/dhcp/dhcpd.leases")]
+    file: PathBuf,
+
+    /// Evaluate the leases at this instant rather than now. Either RFC 3339, or
+    /// "YYYY/MM/DD HH:MM:SS" in UTC as in the leases file
+    #[arg(long, global = true, value_parser = parse_at)]
+    at: Option<DateTime<Utc>>,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Table of all the lease entries in the file
+    List {
+        /// Comma-separated list of the columns to show
+        #[arg(short, long, value_delimiter = ',', value_parser = parse_column)]
+        columns: Option<Vec<Column>>,
+    },
+    /// Table of the effective leases that are bound to a client
+    Active {
+        /// Comma-separated list of the columns to show
+        #[arg(short, long, value_delimiter = ',', value_parser = parse_column)]
+        columns: Option<Vec<Column>>,
+    },
+    /// The effective leases for an IP, a MAC address or a hostname
+    Lookup {
+        /// IP, MAC address or hostname
+        key: String,
+    },
+    /// All of the entries in the file for an IP or a MAC address, in file order
+    History {
+        /// IP or MAC address
+        key: String,
+    },
+    /// Counts of the leases
+    Stats,
+    /// Write the leases to the standard output
+    Export {
+        #[arg(long, value_enum, default_value = "json")]
+        format: Format,
+        /// Only export the effective leases
+        #[arg(long)]
+        effective: bool,
+        /// Comma-separated list of the columns (CSV only)
+        #[arg(short, long, value_delimiter = ',', value_parser = parse_column)]
+        columns: Option<Vec<Column>>,
+    },
+    /// Check that the file can be parsed
+    Validate,
+    /// Differences between the effective leases of two files
+    Diff { old: PathBuf, new: PathBuf },
+    /// Serve the OpenMetrics text of the leases over HTTP at "/metrics". The file is parsed
+    /// again when it has been modified since the last scrape
+    Serve {
+        /// The address to listen on
+        #[arg(long, default_value = "127.0.0.1:9267")]
+        listen: String,
+        /// A pool to group the leases by, as NAME=RANGE where RANGE is a CIDR subnet or
+        /// "FIRST-LAST". Can be repeated
+        #[arg(long, value_parser = parse_pool)]
+        pool: Vec<(String, IpRange)>,
+        /// The prefix length of the subnets to group the leases by when no pool is given
+        #[arg(long, default_value_t = 24, value_parser = clap::value_parser!(u32).range(0..=32))]
+        prefix: u32,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    Json,
+    Ndjson,
+    Csv,
+}
+
+fn parse_at(s: &str) -> Result<DateTime<Utc>, String> {
+    if let Ok(dt) = DateTime::parse_from_rfc3339(s) {
+        return Ok(dt.with_timezone(&Utc));
+    }
+
+    NaiveDateTime::parse_from_str(s, "%Y/%m/%d %H:%M:%S")
+        .map(|dt| DateTime::from_naive_utc_and_offset(dt, Utc))
+        .map_err(|_| format!("'{}' is neither RFC 3339 nor \"YYYY/MM/DD HH:MM:SS\"", s))
+}
+
+fn parse_column(s: &str) -> Result<Column, String> {
+    Column::from(s)
+}
+
+fn parse_pool(s: &str) -> Result<(String, IpRange), String> {
+    let (name, range) = s
+        .split_once('=')
+        .ok_or_else(|| format!("'{}' is not of the form NAME=RANGE", s))?;
+
+    Ok((name.to_owned(), IpRange::from(range)?))
+}
+
+fn load(path: &Path) -> Result<Leases, String> {
+    parser::parse_file(path).map(|res| res.leases)
+}
+
+/// True if the key looks like a MAC address (i.e., "00:ab:d4:41:21:10")
+fn is_mac(key: &str) -> bool {
+    let parts: Vec<&str> = key.split(':').collect();
+
+    parts.len() >= 6
+        && parts
+            .iter()
+            .all(|p| !p.is_empty() && p.len() <= 2 && p.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+/// Select the leases matching an IP or a MAC address exactly.
+fn filter_key(builder: &mut LeaseFilterBuilder, key: &str) -> Result<Leases, String> {
+    let mut leases = Leases::new();
+
+    if key.parse::<IpAddr>().is_ok() {
+        // The "on_ip" filter matches on the prefix
+        for lease in builder.on_ip(key).collect().all() {
+            if lease.ip == key {
+                leases.push(lease);
+            }
+        }
+    } else if is_mac(key) {
+        let mac = key.to_lowercase();
+        for lease in builder.on_mac(&mac).collect().all() {
+            if lease.hardware.as_ref().is_some_and(|h| h.mac == mac) {
+                leases.push(lease);
+            }
+        }
+    } else {
+        return Err(format!("'{}' is not an IP or a MAC address", key));
+    }
+
+    Ok(leases)
+}
+
+fn print_table(leases: &Leases, columns: &[Column]) {
+    if leases.count() == 0 {
+        println!("No leases");
+        return;
+    }
+
+    let rows: Vec<Vec<String>> = leases
+        .all()
+        .iter()
+        .map(|l| columns.iter().map(|c| c.value(l)).collect())
+        .collect();
+
+    let mut widths: Vec<usize> = columns.iter().map(|c| c.name().len()).collect();
+    for row in rows.iter() {
+        for (width, value) in widths.iter_mut().zip(row.iter()) {
+            *width = (*width).max(value.chars().count());
+        }
+    }
+
+    let format_row = |values: Vec<String>| -> String {
+        let cells: Vec<String> = values
+            .iter()
+            .zip(widths.iter())
+            .map(|(v, w)| format!("{:<width$}", v, width = w))
+            .collect();
+        cells.join("  ").trim_end().to_owned()
+    };
+
+    println!("{}", format_row(columns.iter().map(|c| c.name().to_uppercase()).collect()));
+    for row in rows {
+        println!("{}", format_row(row));
+    }
+}
+
+fn stats(leases: &Leases, at: DateTime<Utc>) {
+    let all = leases.all();
+    let ips: HashSet<&str> = all.iter().map(|l| l.ip.as_str()).collect();
+    let macs: HashSet<&str> = all
+        .iter()
+        .filter_map(|l| l.hardware.as_ref().map(|h| h.mac.as_str()))
+        .collect();
+
+    let effective = LeaseFilterBuilder::new(leases).effective().collect();
+    let active = LeaseFilterBuilder::new(leases).effective().on_active_at(Some(at)).collect();
+
+    let mut states: BTreeMap<String, usize> = BTreeMap::new();
+    for lease in effective.all() {
+        let state = lease.binding_state.unwrap_or_else(|| "(none)".to_owned());
+        *states.entry(state).or_insert(0) += 1;
+    }
+
+    println!("Entries:          {}", leases.count());
+    println!("Distinct IPs:     {}", ips.len());
+    println!("Distinct MACs:    {}", macs.len());
+    println!("Effective leases: {}", effective.count());
+    for (state, count) in states {
+        println!("  {:<15} {}", format!("{}:", state), count);
+    }
+    println!("Abandoned:        {}", effective.all().iter().filter(|l| l.abandoned).count());
+    println!("Active at {}: {}", at.format("%Y/%m/%d %H:%M:%S"), active.count());
+}
+
+/// Print the differences between the effective leases of the two files, per IP.
+fn diff(old: &Leases, new: &Leases) {
+    let by_ip = |leases: &Leases| -> HashMap<String, Lease> {
+        LeaseFilterBuilder::new(leases)
+            .effective()
+            .collect()
+            .all()
+            .into_iter()
+            .map(|l| (l.ip.clone(), l))
+            .collect()
+    };
+    let old = by_ip(old);
+    let new = by_ip(new);
+    let ips: BTreeSet<&String> = old.keys().chain(new.keys()).collect();
+
+    let fields = [Column::Mac, Column::BindingState, Column::Hostname, Column::ClientHostname, Column::Ends];
+
+    for ip in ips {
+        match (old.get(ip), new.get(ip)) {
+            (Some(o), None) => println!("- {} {}", ip, Column::Mac.value(o)),
+            (None, Some(n)) => println!("+ {} {}", ip, Column::Mac.value(n)),
+            (Some(o), Some(n)) => {
+                for field in fields.iter() {
+                    let (ov, nv) = (field.value(o), field.value(n));
+                    if ov != nv {
+                        println!("~ {} {}: {} -> {}", ip, field.name(), ov, nv);
+                    }
+                }
+            }
+            (None, None) => (),
+        }
+    }
+}
+
+/// The leases of the last successful parse of the served file.
+struct Scraped {
+    leases: Leases,
+    modified: Option<SystemTime>,
+    parse_errors: u64,
+}
+
+impl Scraped {
+    /// Parse the file again if it has been modified. On failure, the previous leases are kept.
+    fn refresh(&mut self, path: &Path) {
+        let modified = fs::metadata(path).and_then(|m| m.modified()).ok();
+        if modified.is_some() && modified == self.modified {
+            return;
+        }
+
+        match load(path) {
+            Ok(leases) => {
+                self.leases = leases;
+                self.modified = modified;
+            }
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                self.parse_errors += 1;
+            }
+        }
+    }
+}
+
+/// The state shared by the connections of [serve].
+struct Server {
+    path: PathBuf,
+    scraped: Scraped,
+    builder: MetricsBuilder,
+}
+
+impl Server {
+    /// The OpenMetrics text of the leases, parsing the file again if needed.
+    fn metrics(&mut self) -> String {
+        self.scraped.refresh(&self.path);
+        self.builder.parse_errors(self.scraped.parse_errors);
+        if let Some(modified) = self.scraped.modified {
+            self.builder.file_modified(modified);
+        }
+
+        self.builder.render(&self.scraped.leases)
+    }
+}
+
+/// The time allowed to a client to send its request or to receive the response.
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(10);
+/// The maximum size of a request, headers included.
+const MAX_REQUEST: u64 = 64 * 1024;
+
+fn respond(mut stream: TcpStream, server: &Mutex<Server>) -> io::Result<()> {
+    stream.set_read_timeout(Some(CLIENT_TIMEOUT))?;
+    stream.set_write_timeout(Some(CLIENT_TIMEOUT))?;
+
+    let mut reader = BufReader::new((&stream).take(MAX_REQUEST));
+    let mut request = String::new();
+    reader.read_line(&mut request)?;
+
+    // Read the headers up to the blank line, so the client does not get a reset
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 0 && !header.trim_end().is_empty() {
+        header.clear();
+    }
+
+    let target = request.split_whitespace().nth(1).unwrap_or_default();
+    let (status, content_type, body) = if request.starts_with("GET ") && target == "/metrics" {
+        let body = server.lock().unwrap_or_else(|e| e.into_inner()).metrics();
+        ("200 OK", "application/openmetrics-text; version=1.0.0; charset=utf-8", body)
+    } else {
+        ("404 Not Found", "text/plain; charset=utf-8", "Not found\n".to_owned())
+    };
+
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body,
+    )?;
+    stream.flush()
+}
+
+/// Serve the metrics, with a thread for each connection so that a slow client does not hold up
+/// the others.
+fn serve(path: &Path, listen: &str, pools: Vec<(String, IpRange)>, prefix: u32) -> Result<(), String> {
+    let listener = TcpListener::bind(listen).map_err(|e| format!("Failed to listen on {}: {}", listen, e))?;
+
+    let mut builder = MetricsBuilder::new();
+    builder.subnet_prefix(prefix);
+    for (name, range) in pools {
+        builder.pool(&name, range);
+    }
+
+    let mut scraped = Scraped { leases: Leases::new(), modified: None, parse_errors: 0 };
+    scraped.refresh(path);
+    let server = Arc::new(Mutex::new(Server { path: path.to_owned(), scraped, builder }));
+
+    eprintln!("Serving the metrics of {} on http://{}/metrics", path.display(), listen);
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                continue;
+            }
+        };
+
+        let server = Arc::clone(&server);
+        thread::spawn(move || {
+            if let Err(e) = respond(stream, &server) {
+                eprintln!("Error: {}", e);
+            }
+        });
+    }
+
+    Ok(())
+}
+
+fn run(cli: Cli) -> Result<(), String> {
+    let at = cli.at.unwrap_or_else(Utc::now);
+
+    match cli.command {
+        Command::List { columns } => {
+            let leases = load(&cli.file)?;
+            print_table(&leases, columns.as_deref().unwrap_or(&DEFAULT_COLUMNS));
+        }
+        Command::Active { columns } => {
+            let leases = load(&cli.file)?;
+            let active = LeaseFilterBuilder::new(&leases).effective().on_active_at(Some(at)).collect();
+            print_table(&active, columns.as_deref().unwrap_or(&DEFAULT_COLUMNS));
+        }
+        Command::Lookup { key } => {
+            let leases = load(&cli.file)?;
+            let mut builder = LeaseFilterBuilder::new(&leases);
+            builder.effective();
+
+            let found = if key.parse::<IpAddr>().is_ok() || is_mac(&key) {
+                filter_key(&mut builder, &key)?
+            } else {
+                builder.on_hostname(&key).collect()
+            };
+            print_table(&found, &DEFAULT_COLUMNS);
+        }
+        Command::History { key } => {
+            let leases = load(&cli.file)?;
+            let found = filter_key(&mut LeaseFilterBuilder::new(&leases), &key)?;
+            print_table(&found, &DEFAULT_COLUMNS);
+        }
+        Command::Stats => stats(&load(&cli.file)?, at),
+        Command::Export { format, effective, columns } => {
+            let mut leases = load(&cli.file)?;
+            if effective {
+                leases = LeaseFilterBuilder::new(&leases).effective().collect();
+            }
+
+            let out = io::stdout();
+            let res = match format {
+                Format::Json => export::write_json(&leases, out.lock()),
+                Format::Ndjson => export::write_ndjson(&leases, out.lock()),
+                Format::Csv => export::write_csv(&leases, columns.as_deref().unwrap_or(&DEFAULT_COLUMNS), out.lock()),
+            };
+            res.map_err(|e| format!("Failed to export: {}", e))?;
+        }
+        Command::Validate => {
+            let leases = load(&cli.file)?;
+            println!("{}: OK, {} leases", cli.file.display(), leases.count());
+        }
+        Command::Diff { old, new } => diff(&load(&old)?, &load(&new)?),
+        Command::Serve { listen, pool, prefix } => {
+            if cli.at.is_some() {
+                return Err("--at cannot be used with serve".to_owned());
+            }
+            serve(&cli.file, &listen, pool, prefix)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    match run(Cli::parse()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
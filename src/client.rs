@@ -0,0 +1,280 @@
+//! Parser for the leases file of the ISC DHCP client ("dhclient.leases").
+//!
+//! The file shares the grammar of the "dhcpd.conf" file, so it is parsed with the [config]
+//! parser. Each "lease" block is a [ClientLease]:
+//!
+//! ```text
+//! lease {
+//!   interface "eth0";
+//!   fixed-address 192.168.0.2;
+//!   option subnet-mask 255.255.255.0;
+//!   option routers 192.168.0.1;
+//!   renew 2 2019/01/01 21:30:00;
+//!   rebind 2 2019/01/01 21:52:30;
+//!   expire 2 2019/01/01 22:00:00;
+//! }
+//! ```
+//!
+//! dhclient appends a lease each time it gets one, so the file holds the previous leases of the
+//! interfaces too; the last lease of each interface is the current one (see [latest]).
+//!
+//! The leases of systemd-networkd are parsed into the same model by the [networkd] module.
+//!
+//! ```rust
+//! use std::net::Ipv4Addr;
+//! use dhcpd_parser::client;
+//!
+//! let leases = client::parse("lease {
+//!   interface \"eth0\";
+//!   fixed-address 192.168.0.2;
+//!   option routers 192.168.0.1;
+//!   expire 2 2019/01/01 22:00:00;
+//! }").unwrap();
+//!
+//! assert_eq!(leases[0].interface.as_deref(), Some("eth0"));
+//! assert_eq!(leases[0].routers(), [Ipv4Addr::new(192, 168, 0, 1)]);
+//! assert_eq!(leases[0].expire.unwrap().to_rfc3339(), "2019-01-01T22:00:00Z");
+//! ```
+use std::collections::HashMap;
+use std::fs;
+use std::net::Ipv4Addr;
+use std::path::Path;
+use std::str::FromStr;
+
+use chrono::{DateTime, Utc};
+
+use crate::common::Date;
+use crate::config::{self, Block, OptionStatement, Statement};
+use crate::parser::decode_bytes;
+
+pub mod networkd;
+
+/// An option received from the server. The common options are typed, the others are kept as in
+/// the file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DhcpOption {
+    SubnetMask(Ipv4Addr),
+    Routers(Vec<Ipv4Addr>),
+    DomainNameServers(Vec<Ipv4Addr>),
+    NtpServers(Vec<Ipv4Addr>),
+    BroadcastAddress(Ipv4Addr),
+    ServerIdentifier(Ipv4Addr),
+    DomainName(String),
+    DomainSearch(Vec<String>),
+    HostName(String),
+    InterfaceMtu(u16),
+    /// The lifetime of the lease, in seconds
+    LeaseTime(u32),
+    /// The time before renewing the lease (T1), in seconds
+    RenewalTime(u32),
+    /// The time before rebinding the lease (T2), in seconds
+    RebindingTime(u32),
+    MessageType(u8),
+    Other { name: String, value: String },
+}
+
+impl DhcpOption {
+    /// Parse the "option" statement.
+    pub fn from(option: &OptionStatement) -> Result<DhcpOption, String> {
+        let values = option.values();
+        let first = values.first().map(|v| v.as_str()).unwrap_or_default();
+
+        let ip = |v: &str| {
+            v.parse::<Ipv4Addr>()
+                .map_err(|_| format!("'{}' is not a valid IPv4 address for option {}", v, option.name))
+        };
+        let ips = || values.iter().map(|v| ip(v)).collect::<Result<Vec<Ipv4Addr>, String>>();
+
+        Ok(match option.name.as_str() {
+            "subnet-mask" => DhcpOption::SubnetMask(ip(first)?),
+            "routers" => DhcpOption::Routers(ips()?),
+            "domain-name-servers" => DhcpOption::DomainNameServers(ips()?),
+            "ntp-servers" => DhcpOption::NtpServers(ips()?),
+            "broadcast-address" => DhcpOption::BroadcastAddress(ip(first)?),
+            "dhcp-server-identifier" => DhcpOption::ServerIdentifier(ip(first)?),
+            "domain-name" => DhcpOption::DomainName(first.to_owned()),
+            "domain-search" => DhcpOption::DomainSearch(values.clone()),
+            "host-name" => DhcpOption::HostName(first.to_owned()),
+            "interface-mtu" => DhcpOption::InterfaceMtu(number(&option.name, first)?),
+            "dhcp-lease-time" => DhcpOption::LeaseTime(number(&option.name, first)?),
+            "dhcp-renewal-time" => DhcpOption::RenewalTime(number(&option.name, first)?),
+            "dhcp-rebinding-time" => DhcpOption::RebindingTime(number(&option.name, first)?),
+            "dhcp-message-type" => DhcpOption::MessageType(number(&option.name, first)?),
+            name => DhcpOption::Other {
+                name: name.to_owned(),
+                value: option.value.clone(),
+            },
+        })
+    }
+}
+
+pub(crate) fn number<T: FromStr>(name: &str, value: &str) -> Result<T, String> {
+    value
+        .parse::<T>()
+        .map_err(|_| format!("'{}' is not a valid number for option {}", value, name))
+}
+
+/// A lease of the DHCP client.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ClientLease {
+    /// The network interface the lease was acquired on
+    pub interface: Option<String>,
+    /// The address leased to the client
+    pub fixed_address: Option<String>,
+    /// Set for a BOOTP lease
+    pub bootp: bool,
+    pub server_name: Option<String>,
+    pub filename: Option<String>,
+    pub medium: Option<String>,
+    pub options: Vec<DhcpOption>,
+    /// When the client starts renewing the lease with its server
+    pub renew: Option<Date>,
+    /// When the client starts renewing the lease with any server
+    pub rebind: Option<Date>,
+    /// When the lease expires
+    pub expire: Option<Date>,
+}
+
+impl ClientLease {
+    pub fn new() -> ClientLease {
+        ClientLease::default()
+    }
+
+    /// The address leased to the client, if it is an IPv4 address.
+    pub fn ip(&self) -> Option<Ipv4Addr> {
+        self.fixed_address.as_ref()?.parse().ok()
+    }
+
+    pub fn subnet_mask(&self) -> Option<Ipv4Addr> {
+        self.options.iter().find_map(|o| match o {
+            DhcpOption::SubnetMask(mask) => Some(*mask),
+            _ => None,
+        })
+    }
+
+    /// The routers, empty if the option was not received.
+    pub fn routers(&self) -> Vec<Ipv4Addr> {
+        self.options
+            .iter()
+            .find_map(|o| match o {
+                DhcpOption::Routers(routers) => Some(routers.clone()),
+                _ => None,
+            })
+            .unwrap_or_default()
+    }
+
+    /// The DNS servers, empty if the option was not received.
+    pub fn domain_name_servers(&self) -> Vec<Ipv4Addr> {
+        self.options
+            .iter()
+            .find_map(|o| match o {
+                DhcpOption::DomainNameServers(servers) => Some(servers.clone()),
+                _ => None,
+            })
+            .unwrap_or_default()
+    }
+
+    /// The server that handed out the lease.
+    pub fn server_identifier(&self) -> Option<Ipv4Addr> {
+        self.options.iter().find_map(|o| match o {
+            DhcpOption::ServerIdentifier(ip) => Some(*ip),
+            _ => None,
+        })
+    }
+
+    /// The lifetime of the lease in seconds, as sent by the server.
+    pub fn lease_time(&self) -> Option<u32> {
+        self.options.iter().find_map(|o| match o {
+            DhcpOption::LeaseTime(seconds) => Some(*seconds),
+            _ => None,
+        })
+    }
+
+    /// Indicates if the lease has not expired at the instant `dt`.
+    pub fn is_valid_at(&self, dt: DateTime<Utc>) -> bool {
+        self.expire.is_some_and(|expire| expire.to_chrono() > dt)
+    }
+}
+
+/// Parse the value of "renew", "rebind" or "expire": either "weekday date time", "epoch seconds"
+/// or "never" ([Date::NEVER]).
+pub(crate) fn parse_date(name: &str, values: &[String]) -> Result<Option<Date>, String> {
+    match values {
+        [never] if never == "never" => Ok(Some(Date::NEVER)),
+        [epoch, seconds] if epoch == "epoch" => seconds
+            .parse::<i64>()
+            .ok()
+            .and_then(|s| DateTime::<Utc>::from_timestamp(s, 0))
+            .map(|dt| Some(Date::from_chrono(dt)))
+            .ok_or_else(|| format!("'{}' is not a valid {} time", seconds, name)),
+        [weekday, date, time, ..] => Date::from_strs(weekday, date, time).map(Some),
+        _ => Err(format!("Invalid {} time '{}'", name, values.join(" "))),
+    }
+}
+
+fn parse_lease(block: &Block) -> Result<ClientLease, String> {
+    let mut lease = ClientLease::new();
+
+    for statement in block.statements.iter() {
+        match statement {
+            Statement::Option(option) => lease.options.push(DhcpOption::from(option)?),
+            Statement::Parameter(p) => {
+                let value = || p.values.first().cloned();
+                match p.name.as_str() {
+                    "interface" => lease.interface = value(),
+                    "fixed-address" => lease.fixed_address = value(),
+                    "bootp" => lease.bootp = true,
+                    "server-name" => lease.server_name = value(),
+                    "filename" => lease.filename = value(),
+                    "medium" => lease.medium = value(),
+                    "renew" => lease.renew = parse_date(&p.name, &p.values)?,
+                    "rebind" => lease.rebind = parse_date(&p.name, &p.values)?,
+                    "expire" => lease.expire = parse_date(&p.name, &p.values)?,
+                    _ => log::debug!("Ignoring the client lease statement '{}'", p.name),
+                }
+            }
+            _ => log::debug!("Ignoring the client lease statement {:?}", statement),
+        }
+    }
+
+    Ok(lease)
+}
+
+/// Parse the contents of the "dhclient.leases" file. The statements other than the "lease"
+/// blocks (i.e., "default-duid" and the "lease6" blocks) are skipped.
+pub fn parse(input: &str) -> Result<Vec<ClientLease>, String> {
+    let config = config::parse(input)?;
+    let mut leases = Vec::new();
+
+    for statement in config.statements.iter() {
+        if let Statement::Block(block) = statement {
+            if block.words == ["lease"] {
+                let lease = parse_lease(block).map_err(|e| format!("lease {}: {}", leases.len() + 1, e))?;
+                leases.push(lease);
+            }
+        }
+    }
+
+    Ok(leases)
+}
+
+/// Load and parse the leases file of the client (generally "/var/lib/dhcp/dhclient.leases").
+pub fn parse_file<P: AsRef<Path>>(path: P) -> Result<Vec<ClientLease>, String> {
+    let path = path.as_ref();
+    let bytes = fs::read(path).map_err(|e| format!("Failed to open: {}. Error: {}", path.display(), e))?;
+    let contents = decode_bytes(&bytes);
+
+    parse(&contents).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+/// The last lease of each interface, in the order of the file.
+pub fn latest(leases: &[ClientLease]) -> Vec<&ClientLease> {
+    let mut last: HashMap<Option<&str>, usize> = HashMap::new();
+    for (ndx, lease) in leases.iter().enumerate() {
+        last.insert(lease.interface.as_deref(), ndx);
+    }
+
+    let mut latest: Vec<usize> = last.into_values().collect();
+    latest.sort_unstable();
+    latest.into_iter().map(|ndx| &leases[ndx]).collect()
+}
@@ -0,0 +1,124 @@
+//! Parser for the DHCP leases of systemd-networkd ("/run/systemd/netif/leases/<ifindex>").
+//!
+//! The file is a list of `KEY=VALUE` lines, mapped to the [ClientLease] model as:
+//!
+//! * ADDRESS: the "fixed-address"
+//! * NETMASK, ROUTER, DNS, NTP, BROADCAST, SERVER_ADDRESS, DOMAINNAME, DOMAIN_SEARCH_LIST,
+//!   HOSTNAME, MTU, LIFETIME, T1 and T2: the typed [DhcpOption] items
+//! * The other keys (i.e., CLIENTID): [DhcpOption::Other] items named after the key
+//!
+//! The file only has the lifetimes, so the "renew", "rebind" and "expire" times are computed
+//! from the time the lease was acquired: the modification time of the file for [parse_file].
+//!
+//! ```rust
+//! use std::net::Ipv4Addr;
+//! use dhcpd_parser::client::networkd;
+//!
+//! let lease = networkd::parse("ADDRESS=192.168.0.2\nROUTER=192.168.0.1\nLIFETIME=3600\n", None).unwrap();
+//!
+//! assert_eq!(lease.ip(), Some(Ipv4Addr::new(192, 168, 0, 2)));
+//! assert_eq!(lease.routers(), [Ipv4Addr::new(192, 168, 0, 1)]);
+//! assert_eq!(lease.lease_time(), Some(3600));
+//! ```
+use std::fs;
+use std::net::Ipv4Addr;
+use std::path::Path;
+
+use chrono::{DateTime, Duration, Utc};
+
+use crate::client::{number, ClientLease, DhcpOption};
+use crate::common::Date;
+
+fn parse_ip(key: &str, value: &str) -> Result<Ipv4Addr, String> {
+    value
+        .parse::<Ipv4Addr>()
+        .map_err(|_| format!("'{}' is not a valid IPv4 address for {}", value, key))
+}
+
+fn parse_ips(key: &str, value: &str) -> Result<Vec<Ipv4Addr>, String> {
+    value.split_whitespace().map(|v| parse_ip(key, v)).collect()
+}
+
+/// Parse the contents of the lease file. `acquired` is when the lease was acquired, to compute
+/// the "renew", "rebind" and "expire" times; they are left out if None.
+pub fn parse(input: &str, acquired: Option<DateTime<Utc>>) -> Result<ClientLease, String> {
+    let mut lease = ClientLease::new();
+
+    for (ndx, line) in input.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let (key, value) = line
+            .split_once('=')
+            .ok_or_else(|| format!("line {}: Expected KEY=VALUE, found '{}'", ndx + 1, line))?;
+
+        let option = match key {
+            "ADDRESS" => {
+                lease.fixed_address = Some(value.to_owned());
+                continue;
+            }
+            "NETMASK" => parse_ip(key, value).map(DhcpOption::SubnetMask),
+            "ROUTER" => parse_ips(key, value).map(DhcpOption::Routers),
+            "DNS" => parse_ips(key, value).map(DhcpOption::DomainNameServers),
+            "NTP" => parse_ips(key, value).map(DhcpOption::NtpServers),
+            "BROADCAST" => parse_ip(key, value).map(DhcpOption::BroadcastAddress),
+            "SERVER_ADDRESS" => parse_ip(key, value).map(DhcpOption::ServerIdentifier),
+            "DOMAINNAME" => Ok(DhcpOption::DomainName(value.to_owned())),
+            "DOMAIN_SEARCH_LIST" => Ok(DhcpOption::DomainSearch(value.split_whitespace().map(|d| d.to_owned()).collect())),
+            "HOSTNAME" => Ok(DhcpOption::HostName(value.to_owned())),
+            "MTU" => number(key, value).map(DhcpOption::InterfaceMtu),
+            "LIFETIME" => number(key, value).map(DhcpOption::LeaseTime),
+            "T1" => number(key, value).map(DhcpOption::RenewalTime),
+            "T2" => number(key, value).map(DhcpOption::RebindingTime),
+            _ => Ok(DhcpOption::Other {
+                name: key.to_owned(),
+                value: value.to_owned(),
+            }),
+        };
+        lease.options.push(option.map_err(|e| format!("line {}: {}", ndx + 1, e))?);
+    }
+
+    if let Some(acquired) = acquired {
+        let at = |seconds: u32| Date::from_chrono(acquired + Duration::seconds(seconds as i64));
+
+        for option in lease.options.iter() {
+            match option {
+                DhcpOption::RenewalTime(seconds) => lease.renew = Some(at(*seconds)),
+                DhcpOption::RebindingTime(seconds) => lease.rebind = Some(at(*seconds)),
+                DhcpOption::LeaseTime(seconds) => lease.expire = Some(at(*seconds)),
+                _ => (),
+            }
+        }
+    }
+
+    Ok(lease)
+}
+
+/// The name of the network interface with the index, from "/sys/class/net".
+fn interface_name(ifindex: &str) -> Option<String> {
+    fs::read_dir("/sys/class/net").ok()?.flatten().find_map(|entry| {
+        let index = fs::read_to_string(entry.path().join("ifindex")).ok()?;
+        (index.trim() == ifindex).then(|| entry.file_name().to_string_lossy().into_owned())
+    })
+}
+
+/// Load and parse the lease file at `path`.
+///
+/// The interface is named after the index in the name of the file, or is the index itself if
+/// there is no such interface on this host. The lease is taken as acquired when the file was
+/// last modified.
+pub fn parse_file<P: AsRef<Path>>(path: P) -> Result<ClientLease, String> {
+    let path = path.as_ref();
+    let contents = fs::read_to_string(path).map_err(|e| format!("Failed to open: {}. Error: {}", path.display(), e))?;
+    let acquired = fs::metadata(path).and_then(|m| m.modified()).ok().map(DateTime::<Utc>::from);
+
+    let mut lease = parse(&contents, acquired).map_err(|e| format!("{}: {}", path.display(), e))?;
+
+    if let Some(ifindex) = path.file_name().map(|n| n.to_string_lossy().into_owned()) {
+        lease.interface = Some(interface_name(&ifindex).unwrap_or(ifindex));
+    }
+
+    Ok(lease)
+}
@@ -1,5 +1,7 @@
 use std::fmt;
 use std::cmp;
+use std::convert::TryFrom;
+use std::net::Ipv4Addr;
 use chrono::prelude::*;
 use chrono::DateTime;
 
@@ -14,17 +16,36 @@ pub struct Date {
     pub second: i64,
 }
 
-// TODO: This should all really just use the chrono crate... especially since 4660 other crates use it... We'd get serde by default.
+// TODO: This should all really just use the chrono crate... especially since 4660 other crates use it.
 impl Date {
+    /// The end date of a lease that never ends, i.e., "ends never;" of the ISC leases. It is the
+    /// last second of the year 9999, after any other date, so the lease stays active.
+    pub const NEVER: Date = Date {
+        weekday: 5,
+        year: 9999,
+        month: 12,
+        day: 31,
+        hour: 23,
+        minute: 59,
+        second: 59,
+    };
+
     pub fn from<S: Into<String>>(wd: S, d: S, t: S) -> Result<Date, String> {
-        let weekday = wd.into();
-        let date = d.into();
-        let time = t.into();
+        Date::from_strs(&wd.into(), &d.into(), &t.into())
+    }
 
+    /// Same as [Date::from], borrowing the parts rather than taking ownership.
+    pub fn from_strs(weekday: &str, date: &str, time: &str) -> Result<Date, String> {
         // Parses from `weekday year/month/day hour:minute:second` format as
         // specified in OpenBSD man page
+        let number = |name: &str, value: &str| {
+            value
+                .parse::<i64>()
+                .map_err(|_| format!("{} should be a number. {} is not", name, value))
+        };
+
         let mut result = Date::new();
-        result.weekday = weekday.parse::<i64>().expect("Error parsing weekday");
+        result.weekday = number("Weekday", weekday)?;
         if result.weekday < 0 || result.weekday > 6 {
             return Err(format!("Weekday should be a number between 0 and 6. {} is not", weekday));
         }
@@ -33,33 +54,42 @@ impl Date {
         if d.len() != 3 {
             return Err(format!("{} does not have expected date format (YYYY/MM/DD)", date));
         }
-        result.year = d[0].to_string().parse::<i64>().expect("Year should be a number");
-        result.month = d[1].to_string().parse::<i64>().expect("Month should be a number");
-        if result.month < 1 {
-            return Err(format!("Month should be a number >= 1. {} is not", result.month));
+        result.year = number("Year", d[0])?;
+        result.month = number("Month", d[1])?;
+        if result.month < 1 || result.month > 12 {
+            return Err(format!("Month should be a number between 1 and 12. {} is not", result.month));
         }
-        result.day = d[2].to_string().parse::<i64>().expect("Day should be a number");
-        if result.day < 1 {
-            return Err(format!("Day should be a number between >= 1. {} is not", result.day));
+        result.day = number("Day", d[2])?;
+        if result.day < 1 || result.day > 31 {
+            return Err(format!("Day should be a number between 1 and 31. {} is not", result.day));
         }
 
         let t: Vec<&str> = time.split(':').collect();
         if t.len() != 3 {
             return Err(format!("{} does not have expected time format (HH:mm:ss)", time));
         }
-        result.hour = t[0].to_string().parse::<i64>().expect("Hour should be a number");
+        result.hour = number("Hour", t[0])?;
         if result.hour < 0 || result.hour > 23 {
             return Err(format!("Hour should be a number between 0 and 23. {} is not", result.hour));
         }
-        result.minute = t[1].to_string().parse::<i64>().expect("Minute should be a number");
-        if result.minute < 0 || result.hour > 59 {
+        result.minute = number("Minute", t[1])?;
+        if result.minute < 0 || result.minute > 59 {
             return Err(format!("Minute should be a number between 0 and 59. {} is not", result.minute));
         }
-        result.second = t[2].to_string().parse::<i64>().expect("Second should be a number");
-        if result.hour < 0 || result.hour > 59 {
+        result.second = number("Second", t[2])?;
+        if result.second < 0 || result.second > 59 {
             return Err(format!("Second should be a number between 0 and 59. {} is not", result.second));
         }
 
+        // The day of the month and the year are checked together, i.e., 2021/02/30 is invalid
+        let valid = i32::try_from(result.year).ok().and_then(|year| {
+            NaiveDate::from_ymd_opt(year, result.month as u32, result.day as u32)?;
+            NaiveTime::from_hms_opt(result.hour as u32, result.minute as u32, result.second as u32)
+        });
+        if valid.is_none() {
+            return Err(format!("{} {} is not a valid date", date, time));
+        }
+
         Ok(result)
     }
 
@@ -102,10 +132,49 @@ impl Date {
         }
     }
 
-    /// Return self as an instance of chrono DateTime<Utc>
+    /// True for [Date::NEVER].
+    pub fn is_never(&self) -> bool {
+        *self == Date::NEVER
+    }
+
+    /// Format as an RFC 3339 string in UTC (i.e., "2019-01-01T22:00:00Z").
+    pub fn to_rfc3339(&self) -> String {
+        format!(
+            "{}-{:0>2}-{:0>2}T{:0>2}:{:0>2}:{:0>2}Z",
+            self.year, self.month, self.day, self.hour, self.minute, self.second,
+        )
+    }
+
+    /// Create from a chrono DateTime<Utc>, the inverse of [Date::to_chrono].
+    pub fn from_chrono(dt: DateTime<Utc>) -> Date {
+        Date {
+            weekday: dt.weekday().num_days_from_sunday() as i64,
+            year: dt.year() as i64,
+            month: dt.month() as i64,
+            day: dt.day() as i64,
+            hour: dt.hour() as i64,
+            minute: dt.minute() as i64,
+            second: dt.second() as i64,
+        }
+    }
+
+    /// Return self as an instance of chrono DateTime<Utc>. A date that does not exist (i.e., built
+    /// field by field as 2021/02/30) gives the epoch, as a lease without an end date.
     pub fn to_chrono(self) -> DateTime<Utc> {
-        Utc.ymd(self.year as i32, self.month as u32, self.day as u32)
-            .and_hms(self.hour as u32, self.minute as u32, self.second as u32)
+        i32::try_from(self.year)
+            .ok()
+            .and_then(|year| {
+                Utc.with_ymd_and_hms(
+                    year,
+                    self.month as u32,
+                    self.day as u32,
+                    self.hour as u32,
+                    self.minute as u32,
+                    self.second as u32,
+                )
+                .single()
+            })
+            .unwrap_or(DateTime::UNIX_EPOCH)
     }
 }
 
@@ -126,36 +195,114 @@ impl fmt::Display for Date {
 
 impl cmp::PartialOrd for Date {
     fn partial_cmp(&self, other: &Date) -> Option<cmp::Ordering> {
-        if self.year != other.year {
-            return self.year.partial_cmp(&other.year);
-        }
+        Some(self.cmp(other))
+    }
+}
 
-        if self.month != other.month {
-            return self.month.partial_cmp(&other.month);
-        }
+/// Chronological order. The weekday only breaks the tie between equal dates (it is derived
+/// from the date when written by dhcpd), to be consistent with [PartialEq].
+impl cmp::Ord for Date {
+    fn cmp(&self, other: &Date) -> cmp::Ordering {
+        let key = |d: &Date| (d.year, d.month, d.day, d.hour, d.minute, d.second, d.weekday);
+        key(self).cmp(&key(other))
+    }
+}
 
-        if self.day != other.day {
-            return self.day.partial_cmp(&other.day);
+/// Serialized as an RFC 3339 string in UTC (i.e., "2019-01-01T22:00:00Z").
+#[cfg(feature = "serde")]
+impl serde::Serialize for Date {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_rfc3339())
+    }
+}
+
+/// Deserialized from an RFC 3339 string. The value is converted to UTC and the weekday is
+/// computed from the date.
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Date {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Date, D::Error> {
+        let s = <String as serde::Deserialize>::deserialize(deserializer)?;
+        let dt = DateTime::parse_from_rfc3339(&s)
+            .map_err(|e| serde::de::Error::custom(format!("Invalid RFC 3339 date {:?}: {}", s, e)))?
+            .with_timezone(&Utc);
+
+        Ok(Date::from_chrono(dt))
+    }
+}
+
+/// An inclusive range of IPv4 addresses, such as a subnet or the "range" of a pool.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IpRange {
+    /// The first address of the range
+    pub first: Ipv4Addr,
+    /// The last address of the range
+    pub last: Ipv4Addr,
+}
+
+impl IpRange {
+    pub fn new(first: Ipv4Addr, last: Ipv4Addr) -> Result<IpRange, String> {
+        if first > last {
+            return Err(format!("Range start {} is after its end {}", first, last));
         }
 
-        if self.hour != other.hour {
-            return self.hour.partial_cmp(&other.hour);
+        Ok(IpRange { first, last })
+    }
+
+    /// Parses either a subnet in CIDR notation ("192.168.4.0/24") or a range of addresses
+    /// ("192.168.4.100-192.168.4.200").
+    pub fn from(s: &str) -> Result<IpRange, String> {
+        let parse_ip = |ip: &str| {
+            ip.trim()
+                .parse::<Ipv4Addr>()
+                .map_err(|_| format!("'{}' is not a valid IPv4 address", ip))
+        };
+
+        if let Some((network, prefix)) = s.split_once('/') {
+            let prefix = prefix
+                .parse::<u32>()
+                .ok()
+                .filter(|p| *p <= 32)
+                .ok_or_else(|| format!("'{}' is not a valid prefix length", prefix))?;
+            return Ok(IpRange::subnet(parse_ip(network)?, prefix));
         }
 
-        if self.minute != other.minute {
-            return self.minute.partial_cmp(&other.minute);
+        match s.split_once('-') {
+            Some((first, last)) => IpRange::new(parse_ip(first)?, parse_ip(last)?),
+            None => Err(format!("'{}' is neither a CIDR subnet nor a range of addresses", s)),
         }
+    }
+
+    /// The subnet of `prefix` bits containing `ip`.
+    pub fn subnet(ip: Ipv4Addr, prefix: u32) -> IpRange {
+        let mask = u32::MAX.checked_shl(32 - prefix).unwrap_or(0);
+        let network = u32::from(ip) & mask;
 
-        if self.second != other.second {
-            return self.second.partial_cmp(&other.second);
+        IpRange {
+            first: Ipv4Addr::from(network),
+            last: Ipv4Addr::from(network | !mask),
         }
+    }
+
+    pub fn contains(&self, ip: Ipv4Addr) -> bool {
+        self.first <= ip && ip <= self.last
+    }
 
-        None
+    /// The number of addresses in the range
+    pub fn size(&self) -> u64 {
+        (u32::from(self.last) - u32::from(self.first)) as u64 + 1
     }
 }
 
-impl cmp::Ord for Date {
-    fn cmp(&self, other: &Date) -> cmp::Ordering {
-        return self.partial_cmp(other).unwrap();
+impl fmt::Display for IpRange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let size = self.size();
+        let first = u32::from(self.first);
+
+        // Display as CIDR if the range is exactly a subnet
+        if size.is_power_of_two() && (first as u64) & (size - 1) == 0 {
+            write!(f, "{}/{}", self.first, 32 - size.trailing_zeros())
+        } else {
+            write!(f, "{}-{}", self.first, self.last)
+        }
     }
 }
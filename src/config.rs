@@ -0,0 +1,588 @@
+//! Parser for the "dhcpd.conf" configuration file (ISC and OpenBSD).
+//!
+//! The file is tokenized with the same [Lexer] as the leases file, and parsed into a [Config]:
+//! a tree of [Statement] items in which the declarations (subnets, pools, hosts...) hold their
+//! own statements.
+//!
+//! * [parse]: Parse the contents of the file, leaving the "include" statements unresolved
+//! * [parse_file]: Load and parse the file, along with the files it includes
+//!
+//! ```rust
+//! use dhcpd_parser::config::{self, Declaration};
+//!
+//! let config = config::parse("
+//!     default-lease-time 600;
+//!     subnet 192.168.0.0 netmask 255.255.255.0 {
+//!         option routers 192.168.0.1;
+//!         range 192.168.0.100 192.168.0.200;
+//!     }
+//! ").unwrap();
+//!
+//! assert_eq!(config.parameter("default-lease-time").unwrap().values, ["600"]);
+//!
+//! let subnet = config.subnets()[0];
+//! assert_eq!(subnet.ip_range().to_string(), "192.168.0.0/24");
+//! assert_eq!(subnet.option("routers").unwrap().value, "192.168.0.1");
+//! assert_eq!(subnet.ranges()[0].range.to_string(), "192.168.0.100-192.168.0.200");
+//! ```
+//!
+//! The statements that are not declarations are kept as a generic [Parameter] (i.e.,
+//! "default-lease-time 600" or "match if ..."), and the other blocks (i.e., "if" and "on
+//! commit") as a generic [Block]. Only IPv4 declarations are typed.
+use std::fs;
+use std::net::Ipv4Addr;
+use std::path::{Path, PathBuf};
+
+use crate::common::IpRange;
+use crate::leases::Hardware;
+use crate::lex::{Lexer, Token, TokenKind};
+
+/// The parsed configuration file.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Config {
+    pub statements: Vec<Statement>,
+    /// The file the configuration was loaded from, when using [parse_file].
+    pub source: Option<PathBuf>,
+}
+
+/// A statement of the configuration file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Statement {
+    Parameter(Parameter),
+    Option(OptionStatement),
+    Range(Range),
+    Subnet(Subnet),
+    SharedNetwork(SharedNetwork),
+    Pool(Pool),
+    Group(Group),
+    Host(Host),
+    Class(Class),
+    Subclass(Subclass),
+    FailoverPeer(FailoverPeer),
+    Include(Include),
+    Block(Block),
+}
+
+/// A parameter statement, such as "default-lease-time 600;" or "authoritative;".
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Parameter {
+    /// The first word of the statement
+    pub name: String,
+    /// The other words of the statement, without the quotes
+    pub values: Vec<String>,
+}
+
+/// An "option" statement, such as "option domain-name-servers ns1.example.org, ns2.example.org;".
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OptionStatement {
+    pub name: String,
+    /// The text of the value, as in the file
+    pub value: String,
+}
+
+impl OptionStatement {
+    /// The comma-separated items of the value, without the quotes.
+    pub fn values(&self) -> Vec<String> {
+        let mut values = Vec::new();
+        let mut current = String::new();
+        let mut quoted = false;
+        let mut escaped = false;
+
+        for c in self.value.chars() {
+            match c {
+                _ if escaped => {
+                    current.push(c);
+                    escaped = false;
+                }
+                '\\' if quoted => escaped = true,
+                '"' => quoted = !quoted,
+                ',' if !quoted => values.push(std::mem::take(&mut current).trim().to_owned()),
+                _ => current.push(c),
+            }
+        }
+
+        if !current.trim().is_empty() || !values.is_empty() {
+            values.push(current.trim().to_owned());
+        }
+
+        values
+    }
+}
+
+/// A "range" statement giving the addresses of a subnet or a pool.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Range {
+    /// Set by the "dynamic-bootp" flag
+    pub dynamic_bootp: bool,
+    pub range: IpRange,
+}
+
+/// A "subnet" declaration.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Subnet {
+    pub network: Ipv4Addr,
+    pub netmask: Ipv4Addr,
+    pub statements: Vec<Statement>,
+}
+
+impl Subnet {
+    /// The prefix length of the netmask.
+    pub fn prefix(&self) -> u32 {
+        u32::from(self.netmask).leading_ones()
+    }
+
+    /// All of the addresses of the subnet.
+    pub fn ip_range(&self) -> IpRange {
+        IpRange::subnet(self.network, self.prefix())
+    }
+}
+
+/// A "shared-network" declaration, grouping the subnets of the same physical network.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SharedNetwork {
+    pub name: String,
+    pub statements: Vec<Statement>,
+}
+
+/// A "pool" declaration.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pool {
+    pub statements: Vec<Statement>,
+}
+
+/// A "group" declaration, applying its parameters to the declarations it holds.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Group {
+    pub statements: Vec<Statement>,
+}
+
+/// A "host" declaration. The "hardware" and "fixed-address" statements are moved out of the
+/// statements into their own fields.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Host {
+    pub name: String,
+    pub hardware: Option<Hardware>,
+    /// The addresses (or host names) of the "fixed-address" statements
+    pub fixed_addresses: Vec<String>,
+    pub statements: Vec<Statement>,
+}
+
+/// A "class" declaration.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Class {
+    pub name: String,
+    pub statements: Vec<Statement>,
+}
+
+/// A "subclass" declaration or statement, adding the clients with the value to the class.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Subclass {
+    /// The name of the class
+    pub class: String,
+    /// The value matched against the "match" expression of the class
+    pub value: String,
+    pub statements: Vec<Statement>,
+}
+
+/// A "failover peer" declaration.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FailoverPeer {
+    pub name: String,
+    pub statements: Vec<Statement>,
+}
+
+impl FailoverPeer {
+    /// Either "primary" or "secondary", if one of them is stated.
+    pub fn role(&self) -> Option<&str> {
+        ["primary", "secondary"].iter().copied().find(|r| self.parameter(r).is_some())
+    }
+
+    /// The Maximum Client Lead Time, in seconds (primary only).
+    pub fn mclt(&self) -> Option<u64> {
+        self.number("mclt")
+    }
+
+    /// The load balancing split, from 0 to 256 (primary only).
+    pub fn split(&self) -> Option<u64> {
+        self.number("split")
+    }
+
+    fn number(&self, name: &str) -> Option<u64> {
+        self.parameter(name)?.values.first()?.parse().ok()
+    }
+}
+
+/// An "include" statement. The statements of the included file are only loaded by [parse_file].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Include {
+    /// The path, as stated in the file
+    pub path: PathBuf,
+    pub statements: Vec<Statement>,
+}
+
+/// Any other block, such as "if ... { }", "else { }" or "on commit { }".
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Block {
+    /// The words before the opening brace
+    pub words: Vec<String>,
+    pub statements: Vec<Statement>,
+}
+
+/// Access to the statements held by the [Config] and the declarations.
+pub trait Declaration {
+    fn statements(&self) -> &[Statement];
+
+    /// The first parameter statement named `name`, in this declaration only.
+    fn parameter(&self, name: &str) -> Option<&Parameter> {
+        self.statements().iter().find_map(|s| match s {
+            Statement::Parameter(p) if p.name == name => Some(p),
+            _ => None,
+        })
+    }
+
+    /// The first "option" statement named `name`, in this declaration only.
+    fn option(&self, name: &str) -> Option<&OptionStatement> {
+        self.statements().iter().find_map(|s| match s {
+            Statement::Option(o) if o.name == name => Some(o),
+            _ => None,
+        })
+    }
+
+    /// The "subnet" declarations, including the ones in shared networks, groups and included files.
+    fn subnets(&self) -> Vec<&Subnet> {
+        let mut subnets = Vec::new();
+        walk(self.statements(), &mut |s| {
+            if let Statement::Subnet(subnet) = s {
+                subnets.push(subnet);
+            }
+        });
+        subnets
+    }
+
+    /// The "host" declarations, including the nested ones.
+    fn hosts(&self) -> Vec<&Host> {
+        let mut hosts = Vec::new();
+        walk(self.statements(), &mut |s| {
+            if let Statement::Host(host) = s {
+                hosts.push(host);
+            }
+        });
+        hosts
+    }
+
+    /// The "range" statements, including the ones of the nested pools.
+    fn ranges(&self) -> Vec<&Range> {
+        let mut ranges = Vec::new();
+        walk(self.statements(), &mut |s| {
+            if let Statement::Range(range) = s {
+                ranges.push(range);
+            }
+        });
+        ranges
+    }
+
+    /// The "failover peer" declarations, including the nested ones.
+    fn failover_peers(&self) -> Vec<&FailoverPeer> {
+        let mut peers = Vec::new();
+        walk(self.statements(), &mut |s| {
+            if let Statement::FailoverPeer(peer) = s {
+                peers.push(peer);
+            }
+        });
+        peers
+    }
+}
+
+macro_rules! impl_declaration {
+    ($($t:ty),*) => {
+        $(impl Declaration for $t {
+            fn statements(&self) -> &[Statement] {
+                &self.statements
+            }
+        })*
+    };
+}
+
+impl_declaration!(Config, Subnet, SharedNetwork, Pool, Group, Host, Class, Subclass, FailoverPeer, Include, Block);
+
+impl Statement {
+    /// The statements held by the statement, if it is a declaration.
+    pub fn children(&self) -> &[Statement] {
+        match self {
+            Statement::Subnet(d) => &d.statements,
+            Statement::SharedNetwork(d) => &d.statements,
+            Statement::Pool(d) => &d.statements,
+            Statement::Group(d) => &d.statements,
+            Statement::Host(d) => &d.statements,
+            Statement::Class(d) => &d.statements,
+            Statement::Subclass(d) => &d.statements,
+            Statement::FailoverPeer(d) => &d.statements,
+            Statement::Include(d) => &d.statements,
+            Statement::Block(d) => &d.statements,
+            Statement::Parameter(_) | Statement::Option(_) | Statement::Range(_) => &[],
+        }
+    }
+
+    fn children_mut(&mut self) -> Option<&mut Vec<Statement>> {
+        match self {
+            Statement::Subnet(d) => Some(&mut d.statements),
+            Statement::SharedNetwork(d) => Some(&mut d.statements),
+            Statement::Pool(d) => Some(&mut d.statements),
+            Statement::Group(d) => Some(&mut d.statements),
+            Statement::Host(d) => Some(&mut d.statements),
+            Statement::Class(d) => Some(&mut d.statements),
+            Statement::Subclass(d) => Some(&mut d.statements),
+            Statement::FailoverPeer(d) => Some(&mut d.statements),
+            Statement::Include(d) => Some(&mut d.statements),
+            Statement::Block(d) => Some(&mut d.statements),
+            Statement::Parameter(_) | Statement::Option(_) | Statement::Range(_) => None,
+        }
+    }
+}
+
+/// Call `f` for each of the statements, depth first.
+fn walk<'a>(statements: &'a [Statement], f: &mut dyn FnMut(&'a Statement)) {
+    for statement in statements {
+        f(statement);
+        walk(statement.children(), f);
+    }
+}
+
+/// Remove the surrounding quotes of a word and the escapes within them.
+fn unquote(word: &str) -> String {
+    match word.strip_prefix('"').and_then(|w| w.strip_suffix('"')) {
+        Some(inner) => {
+            let mut out = String::with_capacity(inner.len());
+            let mut chars = inner.chars();
+            while let Some(c) = chars.next() {
+                match c {
+                    '\\' => out.extend(chars.next()),
+                    _ => out.push(c),
+                }
+            }
+            out
+        }
+        None => word.to_owned(),
+    }
+}
+
+struct ConfigParser<'a> {
+    input: &'a str,
+    tokens: Vec<Token<'a>>,
+    pos: usize,
+}
+
+impl<'a> ConfigParser<'a> {
+    fn error(&self, offset: usize, message: String) -> String {
+        let line = self.input[..offset].matches('\n').count() + 1;
+        format!("line {}: {}", line, message)
+    }
+
+    /// The offset of the current token, or of the end of the input.
+    fn offset(&self) -> usize {
+        self.tokens.get(self.pos).map_or(self.input.len(), |t| t.span.start)
+    }
+
+    /// Parse the statements up to the closing brace (if `nested`) or the end of the input.
+    fn statements(&mut self, nested: bool) -> Result<Vec<Statement>, String> {
+        let mut statements = Vec::new();
+
+        loop {
+            match self.tokens.get(self.pos).map(|t| &t.kind) {
+                None if nested => return Err(self.error(self.offset(), "Expected '}', found end of input".to_owned())),
+                None => return Ok(statements),
+                Some(TokenKind::Paren('}')) if nested => {
+                    self.pos += 1;
+                    return Ok(statements);
+                }
+                Some(TokenKind::Paren('}')) => return Err(self.error(self.offset(), "Unexpected '}'".to_owned())),
+                Some(TokenKind::Endl) => self.pos += 1,
+                Some(_) => statements.push(self.statement()?),
+            }
+        }
+    }
+
+    fn statement(&mut self) -> Result<Statement, String> {
+        let start = self.pos;
+        while let Some(token) = self.tokens.get(self.pos) {
+            match token.kind {
+                TokenKind::Endl | TokenKind::Paren('{') | TokenKind::Paren('}') => break,
+                _ => self.pos += 1,
+            }
+        }
+
+        let offset = self.tokens[start].span.start;
+        let words: Vec<&'a str> = self.tokens[start..self.pos].iter().map(|t| t.text).collect();
+
+        match self.tokens.get(self.pos).map(|t| &t.kind) {
+            Some(TokenKind::Endl) => {
+                let statement = self.simple(&words, start).map_err(|e| self.error(offset, e));
+                self.pos += 1;
+                statement
+            }
+            Some(TokenKind::Paren('{')) => {
+                self.pos += 1;
+                let statements = self.statements(true)?;
+                declaration(&words, statements).map_err(|e| self.error(offset, e))
+            }
+            _ => Err(self.error(self.offset(), format!("Expected ';' after '{}'", words.join(" ")))),
+        }
+    }
+
+    /// The text of the input from the token at `from` to the one before the current token.
+    fn text(&self, from: usize) -> &'a str {
+        if from >= self.pos {
+            return "";
+        }
+        &self.input[self.tokens[from].span.start..self.tokens[self.pos - 1].span.end]
+    }
+
+    fn simple(&self, words: &[&'a str], start: usize) -> Result<Statement, String> {
+        match words {
+            ["option", name, ..] => Ok(Statement::Option(OptionStatement {
+                name: name.to_string(),
+                value: self.text(start + 2).to_owned(),
+            })),
+            ["range", rest @ ..] => {
+                let (dynamic_bootp, ips) = match rest {
+                    ["dynamic-bootp", ips @ ..] => (true, ips),
+                    ips => (false, ips),
+                };
+                let range = match ips {
+                    [ip] => IpRange::new(parse_ip(ip)?, parse_ip(ip)?)?,
+                    [first, last] => IpRange::new(parse_ip(first)?, parse_ip(last)?)?,
+                    _ => return Err(format!("Invalid range '{}'", words.join(" "))),
+                };
+                Ok(Statement::Range(Range { dynamic_bootp, range }))
+            }
+            ["include", path] => Ok(Statement::Include(Include {
+                path: PathBuf::from(unquote(path)),
+                statements: Vec::new(),
+            })),
+            ["subclass", ..] => declaration(words, Vec::new()),
+            [name, values @ ..] => Ok(Statement::Parameter(Parameter {
+                name: name.to_string(),
+                values: values.iter().map(|v| unquote(v)).collect(),
+            })),
+            [] => Err("Empty statement".to_owned()),
+        }
+    }
+}
+
+fn parse_ip(ip: &str) -> Result<Ipv4Addr, String> {
+    ip.parse::<Ipv4Addr>()
+        .map_err(|_| format!("'{}' is not a valid IPv4 address", ip))
+}
+
+/// Build the declaration for the words before the opening brace.
+fn declaration(words: &[&str], statements: Vec<Statement>) -> Result<Statement, String> {
+    match words {
+        ["subnet", network, "netmask", netmask] => Ok(Statement::Subnet(Subnet {
+            network: parse_ip(network)?,
+            netmask: parse_ip(netmask)?,
+            statements,
+        })),
+        ["subnet", ..] => Err(format!("Invalid subnet declaration '{}'", words.join(" "))),
+        ["shared-network", name] => Ok(Statement::SharedNetwork(SharedNetwork { name: unquote(name), statements })),
+        ["pool"] => Ok(Statement::Pool(Pool { statements })),
+        ["group"] => Ok(Statement::Group(Group { statements })),
+        ["host", name] => Ok(Statement::Host(host(unquote(name), statements)?)),
+        ["class", name] => Ok(Statement::Class(Class { name: unquote(name), statements })),
+        ["subclass", class, value] => Ok(Statement::Subclass(Subclass {
+            class: unquote(class),
+            value: unquote(value),
+            statements,
+        })),
+        ["failover", "peer", name] => Ok(Statement::FailoverPeer(FailoverPeer { name: unquote(name), statements })),
+        _ => Ok(Statement::Block(Block {
+            words: words.iter().map(|w| w.to_string()).collect(),
+            statements,
+        })),
+    }
+}
+
+fn host(name: String, statements: Vec<Statement>) -> Result<Host, String> {
+    let mut host = Host { name, hardware: None, fixed_addresses: Vec::new(), statements: Vec::new() };
+
+    for statement in statements {
+        match statement {
+            Statement::Parameter(p) if p.name == "hardware" => match p.values.as_slice() {
+                [h_type, mac] => {
+                    host.hardware = Some(Hardware { h_type: h_type.clone(), mac: mac.to_lowercase() });
+                }
+                _ => return Err(format!("Invalid hardware statement in host '{}'", host.name)),
+            },
+            Statement::Parameter(p) if p.name == "fixed-address" => {
+                let addresses = p.values.join(" ");
+                host.fixed_addresses.extend(
+                    addresses.split(',').map(|a| a.trim().to_owned()).filter(|a| !a.is_empty()),
+                );
+            }
+            statement => host.statements.push(statement),
+        }
+    }
+
+    Ok(host)
+}
+
+/// Parse the contents of the configuration file.
+///
+/// The "include" statements are kept with no statements; use [parse_file] to load them.
+pub fn parse(input: &str) -> Result<Config, String> {
+    let mut parser = ConfigParser {
+        input,
+        tokens: Lexer::new(input).collect(),
+        pos: 0,
+    };
+
+    Ok(Config {
+        statements: parser.statements(false)?,
+        source: None,
+    })
+}
+
+/// Load and parse the configuration file at `path`, and the files of its "include" statements.
+///
+/// The relative paths of the included files are resolved from the directory of the file that
+/// includes them.
+pub fn parse_file<P: AsRef<Path>>(path: P) -> Result<Config, String> {
+    let path = path.as_ref();
+
+    Ok(Config {
+        statements: load(path, &mut Vec::new())?,
+        source: Some(path.to_path_buf()),
+    })
+}
+
+/// Load the statements of the file, resolving the includes. `stack` holds the files being
+/// loaded, to detect the cycles.
+fn load(path: &Path, stack: &mut Vec<PathBuf>) -> Result<Vec<Statement>, String> {
+    let canonical = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    if stack.contains(&canonical) {
+        return Err(format!("{}: included recursively", path.display()));
+    }
+
+    let contents = fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let mut statements = parse(&contents)
+        .map_err(|e| format!("{}: {}", path.display(), e))?
+        .statements;
+
+    stack.push(canonical);
+    let dir = path.parent().unwrap_or_else(|| Path::new(""));
+    resolve(&mut statements, dir, stack)?;
+    stack.pop();
+
+    Ok(statements)
+}
+
+fn resolve(statements: &mut [Statement], dir: &Path, stack: &mut Vec<PathBuf>) -> Result<(), String> {
+    for statement in statements.iter_mut() {
+        if let Statement::Include(include) = statement {
+            include.statements = load(&dir.join(&include.path), stack)?;
+        } else if let Some(children) = statement.children_mut() {
+            resolve(children, dir, stack)?;
+        }
+    }
+
+    Ok(())
+}
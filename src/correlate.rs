@@ -0,0 +1,235 @@
+//! Cross-checking of the leases against the pools of the configuration.
+//!
+//! The [Correlator] holds the dynamic pools (the "range" statements) and the fixed-address hosts,
+//! either taken from a parsed "dhcpd.conf" with [Correlator::from_config] or supplied with
+//! [Correlator::pool] and [Correlator::host]. Its [Report] gives:
+//!
+//! * The utilisation of each pool, from the size of its ranges
+//! * The effective leases outside of all of the pools
+//! * The fixed addresses of the hosts that are within a pool
+//!
+//! ```rust
+//! use dhcpd_parser::common::IpRange;
+//! use dhcpd_parser::correlate::Correlator;
+//! use dhcpd_parser::parser;
+//!
+//! let leases = parser::parse_file("tests/data/dhcpd-linux.leases").unwrap().leases;
+//! let report = Correlator::new()
+//!     .pool("lan", &[IpRange::from("192.168.4.100-192.168.4.107").unwrap()])
+//!     .report(&leases);
+//!
+//! assert_eq!(report.pools[0].size, 8);
+//! assert_eq!(report.out_of_range[0].ip, "192.168.4.108");
+//! ```
+use std::collections::HashMap;
+use std::net::Ipv4Addr;
+
+use chrono::prelude::*;
+
+use crate::common::IpRange;
+use crate::config::{Config, Declaration, Statement};
+use crate::leases::{Lease, Leases, LeasesMethods};
+use crate::metrics::lease_state;
+use crate::util::LeaseFilterBuilder;
+
+/// The usage of a pool by the effective leases.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PoolUsage {
+    pub name: String,
+    pub ranges: Vec<IpRange>,
+    /// The number of addresses in the ranges
+    pub size: u64,
+    /// The leases bound to a client
+    pub active: u64,
+    /// The abandoned leases, which the server does not hand out until it runs out of addresses
+    pub abandoned: u64,
+    /// The addresses that can be handed out (the size minus the active and abandoned leases)
+    pub free: u64,
+}
+
+impl PoolUsage {
+    /// The ratio of the active leases to the size, from 0.0 to 1.0.
+    pub fn utilisation(&self) -> f64 {
+        if self.size == 0 {
+            0.0
+        } else {
+            self.active as f64 / self.size as f64
+        }
+    }
+}
+
+/// A fixed-address host within a pool.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Collision {
+    /// The name of the host
+    pub host: String,
+    pub ip: Ipv4Addr,
+    /// The name of the pool
+    pub pool: String,
+    /// The effective lease of the address, if it was handed out
+    pub lease: Option<Lease>,
+}
+
+/// The result of [Correlator::report].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Report {
+    /// The pools, in the order they were added
+    pub pools: Vec<PoolUsage>,
+    /// The effective leases outside of all of the pools (excluding the fixed addresses)
+    pub out_of_range: Vec<Lease>,
+    pub collisions: Vec<Collision>,
+}
+
+/// Builder for the [Report] of the leases against the pools and hosts.
+pub struct Correlator {
+    pools: Vec<(String, Vec<IpRange>)>,
+    hosts: Vec<(String, Ipv4Addr)>,
+    at: Option<DateTime<Utc>>,
+}
+
+impl Default for Correlator {
+    fn default() -> Self {
+        Correlator::new()
+    }
+}
+
+impl Correlator {
+    pub fn new() -> Correlator {
+        Correlator {
+            pools: Vec::new(),
+            hosts: Vec::new(),
+            at: None,
+        }
+    }
+
+    /// Take the pools and the hosts from the configuration.
+    ///
+    /// Each "pool" declaration is a pool named after its subnet (or shared network) and its
+    /// position, as in "192.168.0.0/24 pool 2". The ranges stated directly in a subnet are a pool
+    /// named after the subnet. Only the fixed addresses that are IPs are used.
+    pub fn from_config(config: &Config) -> Correlator {
+        let mut correlator = Correlator::new();
+        correlator.add_statements(&config.statements, "global");
+
+        for host in config.hosts() {
+            for address in host.fixed_addresses.iter() {
+                if let Ok(ip) = address.parse::<Ipv4Addr>() {
+                    correlator.host(&host.name, ip);
+                }
+            }
+        }
+
+        correlator
+    }
+
+    fn add_statements(&mut self, statements: &[Statement], scope: &str) {
+        let ranges: Vec<IpRange> = statements
+            .iter()
+            .filter_map(|s| match s {
+                Statement::Range(r) => Some(r.range),
+                _ => None,
+            })
+            .collect();
+        if !ranges.is_empty() {
+            self.pool(scope, &ranges);
+        }
+
+        let mut pools = 0;
+        for statement in statements {
+            match statement {
+                Statement::Subnet(subnet) => self.add_statements(&subnet.statements, &subnet.ip_range().to_string()),
+                Statement::SharedNetwork(network) => self.add_statements(&network.statements, &network.name),
+                Statement::Pool(pool) => {
+                    pools += 1;
+                    let ranges: Vec<IpRange> = pool.ranges().into_iter().map(|r| r.range).collect();
+                    self.pool(&format!("{} pool {}", scope, pools), &ranges);
+                }
+                Statement::Range(_) | Statement::Host(_) => (),
+                statement => self.add_statements(statement.children(), scope),
+            }
+        }
+    }
+
+    /// Add a pool of dynamic addresses.
+    pub fn pool(&mut self, name: &str, ranges: &[IpRange]) -> &mut Self {
+        self.pools.push((name.to_owned(), ranges.to_vec()));
+        self
+    }
+
+    /// Add a fixed address of a host.
+    pub fn host(&mut self, name: &str, ip: Ipv4Addr) -> &mut Self {
+        self.hosts.push((name.to_owned(), ip));
+        self
+    }
+
+    /// Evaluate the leases at the instant `dt` rather than [Utc::now()].
+    pub fn at(&mut self, dt: DateTime<Utc>) -> &mut Self {
+        self.at = Some(dt);
+        self
+    }
+
+    /// The index of the first pool with the address in its ranges.
+    fn pool_of(&self, ip: Ipv4Addr) -> Option<usize> {
+        self.pools
+            .iter()
+            .position(|(_, ranges)| ranges.iter().any(|r| r.contains(ip)))
+    }
+
+    /// Compare the effective leases with the pools and hosts.
+    pub fn report(&self, leases: &Leases) -> Report {
+        let at = self.at.unwrap_or_else(Utc::now);
+        let effective = LeaseFilterBuilder::new(leases).effective().collect().all();
+
+        let mut pools: Vec<PoolUsage> = self
+            .pools
+            .iter()
+            .map(|(name, ranges)| {
+                let size = ranges.iter().map(|r| r.size()).sum();
+                PoolUsage { name: name.clone(), ranges: ranges.clone(), size, active: 0, abandoned: 0, free: size }
+            })
+            .collect();
+
+        let mut out_of_range = Vec::new();
+        let mut by_ip: HashMap<Ipv4Addr, &Lease> = HashMap::new();
+        for lease in effective.iter() {
+            let ip = match lease.ip.parse::<Ipv4Addr>() {
+                Ok(ip) => ip,
+                Err(_) => continue,
+            };
+            by_ip.insert(ip, lease);
+
+            let pool = match self.pool_of(ip) {
+                Some(ndx) => &mut pools[ndx],
+                None => {
+                    if !self.hosts.iter().any(|(_, host)| *host == ip) {
+                        out_of_range.push(lease.clone());
+                    }
+                    continue;
+                }
+            };
+
+            match lease_state(lease, at) {
+                "active" => pool.active += 1,
+                "abandoned" => pool.abandoned += 1,
+                _ => continue,
+            }
+            pool.free = pool.free.saturating_sub(1);
+        }
+
+        let collisions = self
+            .hosts
+            .iter()
+            .filter_map(|(host, ip)| {
+                let ndx = self.pool_of(*ip)?;
+                Some(Collision {
+                    host: host.clone(),
+                    ip: *ip,
+                    pool: self.pools[ndx].0.clone(),
+                    lease: by_ip.get(ip).map(|l| (*l).clone()),
+                })
+            })
+            .collect();
+
+        Report { pools, out_of_range, collisions }
+    }
+}
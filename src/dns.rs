@@ -0,0 +1,289 @@
+//! Generation of DNS data from the leases, for the networks without dynamic DNS updates.
+//!
+//! The [DnsBuilder] names each lease after its `hostname`, or its `client-hostname` when the
+//! server did not assign one, and writes the records as:
+//!
+//! * [DnsBuilder::write_zone]: A (and AAAA for IPv6) records relative to the domain
+//! * [DnsBuilder::write_reverse_zone]: PTR records with absolute "in-addr.arpa." and "ip6.arpa."
+//!   names
+//! * [DnsBuilder::write_hosts]: An "/etc/hosts" file
+//!
+//! The zone files have no SOA or NS records; they are meant to be `$INCLUDE`d in a zone. The
+//! leases are used as given, so they are usually the effective leases that are active (see
+//! [effective](crate::util::LeaseFilterBuilder::effective) and
+//! [on_active_at](crate::util::LeaseFilterBuilder::on_active_at)):
+//!
+//! ```rust
+//! use dhcpd_parser::dns::{DnsBuilder, Duplicates};
+//! use dhcpd_parser::parser;
+//! use dhcpd_parser::util::LeaseFilterBuilder;
+//!
+//! let leases = parser::parse("lease 192.168.0.2 {
+//!     client-hostname \"Bob's_Laptop\";
+//! }").unwrap().leases;
+//! let effective = LeaseFilterBuilder::new(&leases).effective().collect();
+//!
+//! let mut out = Vec::new();
+//! DnsBuilder::new("example.com").duplicates(Duplicates::Suffix).write_hosts(&effective, &mut out).unwrap();
+//!
+//! assert_eq!(String::from_utf8(out).unwrap(), "192.168.0.2\tbob-s-laptop.example.com bob-s-laptop\n");
+//! ```
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::net::IpAddr;
+
+use crate::leases::{Lease, Leases, LeasesMethods};
+
+/// How the names that are not valid DNS labels (letters, digits and hyphens, at most 63
+/// characters, not starting or ending with a hyphen) are handled. Only the part of the name
+/// before the first dot is used, and the names are lowercased in both cases.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sanitize {
+    /// Replace the runs of invalid characters with a hyphen and truncate to 63 characters
+    Replace,
+    /// Leave out the leases with an invalid name
+    Skip,
+}
+
+/// How several leases with the same name are handled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Duplicates {
+    /// Keep all of the leases, giving several addresses to the name
+    All,
+    /// Keep the first lease, in the order of the [Leases]
+    First,
+    /// Keep the lease that started last (or the last one in order if they started together)
+    Latest,
+    /// Keep all of the leases, appending "-2", "-3"... to the name of the second, third... lease
+    Suffix,
+    /// Leave out all of the leases sharing a name
+    Drop,
+}
+
+/// A name and its address, as written to the zone and hosts files.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Record {
+    /// The host name, relative to the domain
+    pub name: String,
+    pub ip: IpAddr,
+}
+
+/// Builder for the DNS data of the [Leases], with the usual chained settings.
+pub struct DnsBuilder {
+    domain: String,
+    ttl: u32,
+    sanitize: Sanitize,
+    duplicates: Duplicates,
+}
+
+impl DnsBuilder {
+    /// Generate the names in `domain` (i.e., "example.com").
+    pub fn new(domain: &str) -> DnsBuilder {
+        DnsBuilder {
+            domain: domain.trim_matches('.').to_lowercase(),
+            ttl: 3600,
+            sanitize: Sanitize::Replace,
+            duplicates: Duplicates::Latest,
+        }
+    }
+
+    /// The TTL of the records, in seconds (3600 by default).
+    pub fn ttl(&mut self, ttl: u32) -> &mut Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// The handling of invalid names ([Sanitize::Replace] by default).
+    pub fn sanitize(&mut self, sanitize: Sanitize) -> &mut Self {
+        self.sanitize = sanitize;
+        self
+    }
+
+    /// The handling of duplicate names ([Duplicates::Latest] by default).
+    pub fn duplicates(&mut self, duplicates: Duplicates) -> &mut Self {
+        self.duplicates = duplicates;
+        self
+    }
+
+    fn fqdn(&self, name: &str) -> String {
+        if self.domain.is_empty() {
+            name.to_owned()
+        } else {
+            format!("{}.{}", name, self.domain)
+        }
+    }
+
+    /// The records of the leases, in the order of the leases. The leases without a name or with
+    /// an address that is not an IP are left out.
+    pub fn records(&self, leases: &Leases) -> Vec<Record> {
+        let mut named: Vec<(String, IpAddr, Lease)> = Vec::new();
+        for lease in leases.all() {
+            let name = lease.hostname.as_deref().or(lease.client_hostname.as_deref());
+            let name = match name.and_then(|n| sanitize_label(n, self.sanitize)) {
+                Some(name) => name,
+                None => continue,
+            };
+
+            if let Ok(ip) = lease.ip.parse::<IpAddr>() {
+                named.push((name, ip, lease));
+            }
+        }
+
+        let mut counts: HashMap<String, usize> = HashMap::new();
+        for (name, _, _) in named.iter() {
+            *counts.entry(name.clone()).or_insert(0) += 1;
+        }
+
+        // The index of the lease kept for each duplicate name
+        let mut kept: HashMap<&str, usize> = HashMap::new();
+        for (ndx, (name, _, lease)) in named.iter().enumerate() {
+            let keep = match kept.get(name.as_str()) {
+                None => true,
+                Some(_) if self.duplicates == Duplicates::First => false,
+                Some(other) => started_before(&named[*other].2, lease) != Ordering::Greater,
+            };
+
+            if keep {
+                kept.insert(name, ndx);
+            }
+        }
+
+        let mut seen: HashMap<&str, usize> = HashMap::new();
+        let mut records: Vec<Record> = Vec::new();
+        for (ndx, (name, ip, _)) in named.iter().enumerate() {
+            let name = match self.duplicates {
+                Duplicates::All => name.clone(),
+                Duplicates::First | Duplicates::Latest if kept[name.as_str()] == ndx => name.clone(),
+                Duplicates::First | Duplicates::Latest => continue,
+                Duplicates::Drop if counts[name] == 1 => name.clone(),
+                Duplicates::Drop => continue,
+                Duplicates::Suffix => {
+                    let seen = seen.entry(name).or_insert(0);
+                    *seen += 1;
+                    if *seen == 1 {
+                        name.clone()
+                    } else {
+                        // Skip the suffixes taken by other names (i.e., "host-2" in the leases)
+                        loop {
+                            let suffix = format!("-{}", seen);
+                            let candidate = format!("{}{}", truncate(name, 63 - suffix.len()).trim_end_matches('-'), suffix);
+                            if !counts.contains_key(&candidate) && !records.iter().any(|r| r.name == candidate) {
+                                break candidate;
+                            }
+                            *seen += 1;
+                        }
+                    }
+                }
+            };
+
+            records.push(Record { name, ip: *ip });
+        }
+
+        records
+    }
+
+    /// Write the A and AAAA records, relative to the domain.
+    pub fn write_zone<W: Write>(&self, leases: &Leases, mut writer: W) -> io::Result<()> {
+        if !self.domain.is_empty() {
+            writeln!(writer, "$ORIGIN {}.", self.domain)?;
+        }
+        writeln!(writer, "$TTL {}", self.ttl)?;
+
+        for record in self.records(leases) {
+            let kind = if record.ip.is_ipv4() { "A" } else { "AAAA" };
+            writeln!(writer, "{}\tIN\t{}\t{}", record.name, kind, record.ip)?;
+        }
+
+        writer.flush()
+    }
+
+    /// Write the PTR records, with absolute names since the addresses usually span several
+    /// reverse zones.
+    pub fn write_reverse_zone<W: Write>(&self, leases: &Leases, mut writer: W) -> io::Result<()> {
+        writeln!(writer, "$TTL {}", self.ttl)?;
+
+        for record in self.records(leases) {
+            writeln!(writer, "{}\tIN\tPTR\t{}.", reverse_name(record.ip), self.fqdn(&record.name))?;
+        }
+
+        writer.flush()
+    }
+
+    /// Write the records as an "/etc/hosts" file, with the fully qualified name first.
+    pub fn write_hosts<W: Write>(&self, leases: &Leases, mut writer: W) -> io::Result<()> {
+        for record in self.records(leases) {
+            if self.domain.is_empty() {
+                writeln!(writer, "{}\t{}", record.ip, record.name)?;
+            } else {
+                writeln!(writer, "{}\t{} {}", record.ip, self.fqdn(&record.name), record.name)?;
+            }
+        }
+
+        writer.flush()
+    }
+}
+
+/// Compare the start of the leases, the leases without a start being the earliest.
+fn started_before(a: &Lease, b: &Lease) -> Ordering {
+    match (a.dates.starts, b.dates.starts) {
+        (Some(a), Some(b)) => a.partial_cmp(&b).unwrap_or(Ordering::Equal),
+        (a, b) => a.is_some().cmp(&b.is_some()),
+    }
+}
+
+fn truncate(s: &str, len: usize) -> &str {
+    // The labels are ASCII
+    &s[..s.len().min(len)]
+}
+
+/// The name as a DNS label, or None if it cannot be one.
+pub fn sanitize_label(name: &str, sanitize: Sanitize) -> Option<String> {
+    let name = name.split('.').next().unwrap_or_default().to_lowercase();
+
+    let label = match sanitize {
+        Sanitize::Skip => name,
+        Sanitize::Replace => {
+            let mut label = String::with_capacity(name.len());
+            for c in name.chars() {
+                if c.is_ascii_alphanumeric() {
+                    label.push(c);
+                } else if !label.ends_with('-') {
+                    label.push('-');
+                }
+            }
+
+            truncate(label.trim_matches('-'), 63).trim_end_matches('-').to_owned()
+        }
+    };
+
+    let valid = !label.is_empty()
+        && label.len() <= 63
+        && !label.starts_with('-')
+        && !label.ends_with('-')
+        && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-');
+
+    if valid {
+        Some(label)
+    } else {
+        None
+    }
+}
+
+/// The absolute name of the address in the "in-addr.arpa." or "ip6.arpa." reverse tree.
+pub fn reverse_name(ip: IpAddr) -> String {
+    match ip {
+        IpAddr::V4(ip) => {
+            let o = ip.octets();
+            format!("{}.{}.{}.{}.in-addr.arpa.", o[3], o[2], o[1], o[0])
+        }
+        IpAddr::V6(ip) => {
+            let mut name = String::with_capacity(72);
+            for byte in ip.octets().iter().rev() {
+                name.push_str(&format!("{:x}.{:x}.", byte & 0xf, byte >> 4));
+            }
+            name.push_str("ip6.arpa.");
+            name
+        }
+    }
+}
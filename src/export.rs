@@ -0,0 +1,297 @@
+//! Export of the [Leases] to other formats.
+//!
+//! * [write_csv]: CSV with a configurable set of [Column] items
+//! * [write_json]: Pretty-printed JSON array (requires the "json" feature)
+//! * [write_ndjson]: Newline-delimited JSON, one lease per line (requires the "json" feature)
+//! * [write_leases]: The "dhcpd.leases" format of a [Dialect]
+//!
+//! The dates are written as RFC 3339 strings in UTC (i.e., "2019-01-01T22:00:00Z"), except by
+//! [write_leases] which follows the format of dhcpd.
+use std::io::{self, Write};
+
+use crate::common::Date;
+use crate::formats::kea::client_id;
+use crate::leases::{Lease, Leases, LeasesMethods};
+use crate::parser::Dialect;
+
+/// The columns that can be exported to CSV.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Column {
+    Ip,
+    Mac,
+    Hostname,
+    ClientHostname,
+    Uid,
+    BindingState,
+    Starts,
+    Ends,
+    Cltt,
+}
+
+/// All of the columns, in the default order.
+pub const DEFAULT_COLUMNS: [Column; 9] = [
+    Column::Ip,
+    Column::Mac,
+    Column::Hostname,
+    Column::ClientHostname,
+    Column::Uid,
+    Column::BindingState,
+    Column::Starts,
+    Column::Ends,
+    Column::Cltt,
+];
+
+impl Column {
+    /// The name of the column, used for the CSV header.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Column::Ip => "ip",
+            Column::Mac => "mac",
+            Column::Hostname => "hostname",
+            Column::ClientHostname => "client-hostname",
+            Column::Uid => "uid",
+            Column::BindingState => "binding-state",
+            Column::Starts => "starts",
+            Column::Ends => "ends",
+            Column::Cltt => "cltt",
+        }
+    }
+
+    pub fn from(s: &str) -> Result<Column, String> {
+        DEFAULT_COLUMNS
+            .iter()
+            .find(|c| c.name() == s)
+            .copied()
+            .ok_or_else(|| format!("'{}' is not a recognized column", s))
+    }
+
+    /// The value of the column for the lease. Missing values are empty.
+    pub fn value(&self, lease: &Lease) -> String {
+        let date = |d: Option<Date>| d.map(|d| d.to_rfc3339()).unwrap_or_default();
+
+        match self {
+            Column::Ip => lease.ip.clone(),
+            Column::Mac => lease.hardware.as_ref().map(|h| h.mac.clone()).unwrap_or_default(),
+            Column::Hostname => lease.hostname.clone().unwrap_or_default(),
+            Column::ClientHostname => lease.client_hostname.clone().unwrap_or_default(),
+            Column::Uid => lease.uid.clone().unwrap_or_default(),
+            Column::BindingState => lease.binding_state.clone().unwrap_or_default(),
+            Column::Starts => date(lease.dates.starts),
+            Column::Ends => date(lease.dates.ends),
+            Column::Cltt => date(lease.dates.cltt),
+        }
+    }
+}
+
+/// Quote the CSV field if it contains a separator, a quote or a line break (RFC 4180).
+fn escape_csv(field: &str) -> String {
+    if field.contains(&[',', '"', '\n', '\r'][..]) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+/// Write the leases as CSV with a header line, one lease per line.
+///
+/// ```rust
+/// use dhcpd_parser::export::{write_csv, Column};
+/// use dhcpd_parser::parser;
+///
+/// let leases = parser::parse("lease 192.168.0.2 {
+///     hardware ethernet 11:11:11:11:11:11;
+///     client-hostname \"my,host\";
+/// }").unwrap().leases;
+///
+/// let mut out = Vec::new();
+/// write_csv(&leases, &[Column::Ip, Column::ClientHostname], &mut out).unwrap();
+///
+/// assert_eq!(String::from_utf8(out).unwrap(), "ip,client-hostname\n192.168.0.2,\"my,host\"\n");
+/// ```
+pub fn write_csv<W: Write>(leases: &Leases, columns: &[Column], mut writer: W) -> io::Result<()> {
+    let header: Vec<&str> = columns.iter().map(|c| c.name()).collect();
+    writeln!(writer, "{}", header.join(","))?;
+
+    for ndx in 0..leases.count() {
+        let row: Vec<String> = columns
+            .iter()
+            .map(|c| escape_csv(&c.value(&leases[ndx])))
+            .collect();
+        writeln!(writer, "{}", row.join(","))?;
+    }
+
+    writer.flush()
+}
+
+/// Write the leases as a pretty-printed JSON array (requires the "json" feature).
+#[cfg(feature = "json")]
+pub fn write_json<W: Write>(leases: &Leases, mut writer: W) -> io::Result<()> {
+    serde_json::to_writer_pretty(&mut writer, leases)?;
+    writeln!(writer)?;
+    writer.flush()
+}
+
+/// Write the leases as newline-delimited JSON, one lease object per line (requires the "json"
+/// feature). This is the format expected by most log shippers.
+#[cfg(feature = "json")]
+pub fn write_ndjson<W: Write>(leases: &Leases, mut writer: W) -> io::Result<()> {
+    for ndx in 0..leases.count() {
+        serde_json::to_writer(&mut writer, &leases[ndx])?;
+        writeln!(writer)?;
+    }
+
+    writer.flush()
+}
+
+/// Format the date as dhcpd writes it, i.e., "2 2019/01/01 22:00:00", or "never" for
+/// [Date::NEVER] (except for OpenBSD, which has no "never").
+fn leases_date(date: &Date, dialect: Dialect) -> String {
+    let utc = if dialect == Dialect::OpenBsd { " UTC" } else { "" };
+    if date.is_never() && dialect != Dialect::OpenBsd {
+        return "never".to_owned();
+    }
+
+    format!(
+        "{} {}/{:0>2}/{:0>2} {:0>2}:{:0>2}:{:0>2}{}",
+        date.weekday, date.year, date.month, date.day, date.hour, date.minute, date.second, utc,
+    )
+}
+
+/// Write a DHCPv4 lease with the statements of the `dialect`.
+fn write_lease<W: Write>(lease: &Lease, dialect: Dialect, writer: &mut W) -> io::Result<()> {
+    let dates = [
+        ("starts", lease.dates.starts),
+        ("ends", lease.dates.ends),
+        ("tstp", lease.dates.tstp),
+        ("tsfp", lease.dates.tsfp),
+        ("atsfp", lease.dates.atsfp),
+        ("cltt", lease.dates.cltt),
+    ];
+
+    writeln!(writer, "lease {} {{", lease.ip)?;
+    for (name, date) in dates.iter() {
+        let failover = *name != "starts" && *name != "ends";
+        if let (Some(date), false) = (date, failover && dialect == Dialect::OpenBsd) {
+            writeln!(writer, "  {} {};", name, leases_date(date, dialect))?;
+        }
+    }
+
+    if dialect != Dialect::OpenBsd {
+        let abandoned = if lease.abandoned { Some("abandoned".to_owned()) } else { None };
+        if let Some(state) = lease.binding_state.clone().or(abandoned) {
+            writeln!(writer, "  binding state {};", state)?;
+        }
+        if let Some(state) = &lease.next_binding_state {
+            writeln!(writer, "  next binding state {};", state)?;
+        }
+        if let Some(state) = &lease.rewind_binding_state {
+            writeln!(writer, "  rewind binding state {};", state)?;
+        }
+    }
+
+    if let Some(hardware) = &lease.hardware {
+        writeln!(writer, "  hardware {} {};", hardware.h_type, hardware.mac)?;
+    }
+    if let Some(uid) = &lease.uid {
+        writeln!(writer, "  uid {};", uid)?;
+    }
+    if let Some(hostname) = &lease.client_hostname {
+        writeln!(writer, "  client-hostname \"{}\";", hostname)?;
+    }
+    if dialect == Dialect::OpenBsd {
+        if let Some(hostname) = &lease.hostname {
+            writeln!(writer, "  hostname \"{}\";", hostname)?;
+        }
+        if lease.abandoned {
+            writeln!(writer, "  abandoned;")?;
+        }
+    }
+
+    writeln!(writer, "}}")
+}
+
+/// The "uid" as the quoted IAID and DUID of an IA declaration, with the non-printable bytes
+/// (and '"', '\\') as octal escapes as dhcpd writes them. A quoted "uid" is kept.
+fn ia_string(uid: &str) -> String {
+    if uid.len() >= 2 && uid.starts_with('"') && uid.ends_with('"') {
+        return uid.to_owned();
+    }
+
+    let id = client_id(uid);
+    let bytes = id.split(':').filter(|b| !b.is_empty()).map(|b| u8::from_str_radix(b, 16).unwrap_or_default());
+    let escaped: String = bytes
+        .map(|b| match b {
+            0x20..=0x7e if b != b'"' && b != b'\\' => (b as char).to_string(),
+            _ => format!("\\{:03o}", b),
+        })
+        .collect();
+    format!("\"{}\"", escaped)
+}
+
+/// Write a DHCPv6 lease as an IA declaration: "ia-pd" for a prefix, otherwise "ia-na".
+fn write_ia<W: Write>(lease: &Lease, writer: &mut W) -> io::Result<()> {
+    let (ia, address) = if lease.ip.contains('/') { ("ia-pd", "iaprefix") } else { ("ia-na", "iaaddr") };
+
+    writeln!(writer, "{} {} {{", ia, ia_string(lease.uid.as_deref().unwrap_or("")))?;
+    if let Some(cltt) = &lease.dates.cltt {
+        writeln!(writer, "  cltt {};", leases_date(cltt, Dialect::IscV6))?;
+    }
+    writeln!(writer, "  {} {} {{", address, lease.ip)?;
+    if let Some(state) = &lease.binding_state {
+        writeln!(writer, "    binding state {};", state)?;
+    }
+    if let Some(ends) = &lease.dates.ends {
+        writeln!(writer, "    ends {};", leases_date(ends, Dialect::IscV6))?;
+    }
+    writeln!(writer, "  }}")?;
+    writeln!(writer, "}}")
+}
+
+/// Write the leases in the "dhcpd.leases" format of the `dialect`, which can be loaded again with
+/// the [parser](crate::parser).
+///
+/// Only the statements of the dialect are written (see [parse_with](crate::parser::parse_with)):
+///
+/// * OpenBsd: the failover dates and the binding states are dropped
+/// * Isc: the "hostname" is dropped, and an abandoned lease without a binding state is written
+///   as "binding state abandoned". The "authoring-byte-order" of the first lease is written first.
+/// * IscV6: each lease is an IA declaration with the "uid" as the IA (a quoted string), holding its address (or
+///   prefix) with the binding state and the end date
+///
+/// ```rust
+/// use dhcpd_parser::export::write_leases;
+/// use dhcpd_parser::parser::{self, Dialect};
+///
+/// let leases = parser::parse("lease 192.168.0.2 {
+///     starts 2 2019/01/01 22:00:00 UTC;
+///     hardware ethernet 11:11:11:11:11:11;
+///     abandoned;
+/// }").unwrap().leases;
+///
+/// let mut out = Vec::new();
+/// write_leases(&leases, Dialect::Isc, &mut out).unwrap();
+///
+/// assert_eq!(String::from_utf8(out).unwrap(), "lease 192.168.0.2 {
+///   starts 2 2019/01/01 22:00:00;
+///   binding state abandoned;
+///   hardware ethernet 11:11:11:11:11:11;
+/// }
+/// ");
+/// ```
+pub fn write_leases<W: Write>(leases: &Leases, dialect: Dialect, mut writer: W) -> io::Result<()> {
+    if dialect != Dialect::OpenBsd {
+        let byte_order = (0..leases.count()).find_map(|ndx| leases[ndx].byte_order.clone());
+        if let Some(byte_order) = byte_order {
+            writeln!(writer, "authoring-byte-order {};\n", byte_order)?;
+        }
+    }
+
+    for ndx in 0..leases.count() {
+        match dialect {
+            Dialect::OpenBsd | Dialect::Isc => write_lease(&leases[ndx], dialect, &mut writer)?,
+            Dialect::IscV6 => write_ia(&leases[ndx], &mut writer)?,
+        }
+    }
+
+    writer.flush()
+}
@@ -0,0 +1,198 @@
+//! Consistency check of the leases of two failover partners, to diagnose a split-brain after a
+//! network partition.
+//!
+//! Both partners of a "failover peer" write their own leases file, which are parsed separately.
+//! The [FailoverCheck] compares the effective leases (the last entry for each IP, see
+//! [LeaseFilterBuilder::effective]) of the two files and reports:
+//!
+//! * The leases where the partners disagree on the binding state, on the owner of a free lease
+//!   ("free" for the primary, "backup" for the secondary) or on the client of the lease
+//! * The IPs with a lease in only one of the files
+//! * The leases where the expiry acknowledged by the peer ("tsfp") lags the expiry sent to the
+//!   peer ("tstp") by more than the MCLT, which the server never lets happen while the partners
+//!   communicate
+//! * The leases sent to the peer ("tstp") without the actual time sent from the peer ("atsfp")
+//!
+//! The MCLT (Maximum Client Lead Time) is the "mclt" of the failover peer in the configuration
+//! of the primary, see [FailoverPeer::mclt](crate::config::FailoverPeer::mclt).
+//!
+//! ```rust
+//! use dhcpd_parser::failover::FailoverCheck;
+//! use dhcpd_parser::parser;
+//!
+//! let primary = parser::parse_file("tests/data/dhcpd-linux.leases").unwrap().leases;
+//! let secondary = parser::parse_file("tests/data/dhcpd-linux.leases").unwrap().leases;
+//! let report = FailoverCheck::new().mclt(1800).report(&primary, &secondary);
+//!
+//! assert!(report.conflicts.is_empty());
+//! // The file was written without a failover peer
+//! assert_eq!(report.missing_atsfp.len(), 10);
+//! ```
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::leases::{Lease, Leases, LeasesMethods};
+use crate::util::LeaseFilterBuilder;
+
+/// The MCLT when none is given, the value recommended by the dhcpd.conf manual page.
+const DEFAULT_MCLT: u64 = 3600;
+
+/// A partner of the failover peer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Side {
+    Primary,
+    Secondary,
+}
+
+impl fmt::Display for Side {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Side::Primary => write!(f, "primary"),
+            Side::Secondary => write!(f, "secondary"),
+        }
+    }
+}
+
+/// What the partners disagree on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ConflictKind {
+    /// The binding states differ (other than [ConflictKind::Owner])
+    BindingState,
+    /// One partner owns the free lease ("free") and the other too ("backup")
+    Owner,
+    /// Both leases are active, for different clients (MAC address, or uid)
+    Client,
+}
+
+/// A lease the partners disagree on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conflict {
+    pub kind: ConflictKind,
+    pub primary: Lease,
+    pub secondary: Lease,
+}
+
+/// A lease with "tsfp" lagging "tstp" by more than the MCLT.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lag {
+    /// The partner whose file holds the lease
+    pub side: Side,
+    pub lease: Lease,
+    /// "tstp" minus "tsfp", in seconds
+    pub seconds: i64,
+}
+
+/// The result of [FailoverCheck::report]. The leases are in the order of the files.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FailoverReport {
+    pub conflicts: Vec<Conflict>,
+    /// The leases of the primary for IPs without a lease in the file of the secondary
+    pub only_primary: Vec<Lease>,
+    /// The leases of the secondary for IPs without a lease in the file of the primary
+    pub only_secondary: Vec<Lease>,
+    pub lagging: Vec<Lag>,
+    /// The leases with "tstp" and without "atsfp"
+    pub missing_atsfp: Vec<(Side, Lease)>,
+}
+
+/// The client of a lease: the MAC address, or the uid if there is no hardware entry.
+fn client(lease: &Lease) -> Option<String> {
+    match (&lease.hardware, &lease.uid) {
+        (Some(hw), _) => Some(hw.mac.to_lowercase()),
+        (None, uid) => uid.clone(),
+    }
+}
+
+/// The binding state of a lease, "abandoned" for the BSD flag.
+fn binding_state(lease: &Lease) -> Option<&str> {
+    if lease.abandoned {
+        return Some("abandoned");
+    }
+    lease.binding_state.as_deref()
+}
+
+/// Builder for the [FailoverReport] of the leases of the two partners.
+pub struct FailoverCheck {
+    mclt: u64,
+}
+
+impl Default for FailoverCheck {
+    fn default() -> Self {
+        FailoverCheck::new()
+    }
+}
+
+impl FailoverCheck {
+    pub fn new() -> FailoverCheck {
+        FailoverCheck { mclt: DEFAULT_MCLT }
+    }
+
+    /// The MCLT of the failover peer, in seconds (3600 by default).
+    pub fn mclt(&mut self, seconds: u64) -> &mut Self {
+        self.mclt = seconds;
+        self
+    }
+
+    fn conflict(&self, primary: &Lease, secondary: &Lease) -> Option<ConflictKind> {
+        match (binding_state(primary), binding_state(secondary)) {
+            (Some("free"), Some("backup")) | (Some("backup"), Some("free")) => Some(ConflictKind::Owner),
+            (p, s) if p != s => Some(ConflictKind::BindingState),
+            (Some("active"), _) if client(primary) != client(secondary) => Some(ConflictKind::Client),
+            _ => None,
+        }
+    }
+
+    /// Check the failover timestamps of the leases of a partner.
+    fn check_dates(&self, side: Side, leases: &[Lease], report: &mut FailoverReport) {
+        for lease in leases {
+            let tstp = match lease.dates.tstp {
+                Some(tstp) => tstp.to_chrono(),
+                None => continue,
+            };
+
+            if let Some(tsfp) = lease.dates.tsfp {
+                let seconds = (tstp - tsfp.to_chrono()).num_seconds();
+                if seconds > self.mclt as i64 {
+                    report.lagging.push(Lag { side, lease: lease.clone(), seconds });
+                }
+            }
+            if lease.dates.atsfp.is_none() {
+                report.missing_atsfp.push((side, lease.clone()));
+            }
+        }
+    }
+
+    /// Compare the effective leases of the primary with those of the secondary.
+    pub fn report(&self, primary: &Leases, secondary: &Leases) -> FailoverReport {
+        let primary = LeaseFilterBuilder::new(primary).effective().collect().all();
+        let secondary = LeaseFilterBuilder::new(secondary).effective().collect().all();
+
+        let mut report = FailoverReport {
+            conflicts: Vec::new(),
+            only_primary: Vec::new(),
+            only_secondary: Vec::new(),
+            lagging: Vec::new(),
+            missing_atsfp: Vec::new(),
+        };
+
+        let by_ip: HashMap<&str, &Lease> = secondary.iter().map(|l| (l.ip.as_str(), l)).collect();
+        for lease in primary.iter() {
+            match by_ip.get(lease.ip.as_str()) {
+                None => report.only_primary.push(lease.clone()),
+                Some(other) => {
+                    if let Some(kind) = self.conflict(lease, other) {
+                        report.conflicts.push(Conflict { kind, primary: lease.clone(), secondary: (*other).clone() });
+                    }
+                }
+            }
+        }
+
+        let by_ip: HashMap<&str, &Lease> = primary.iter().map(|l| (l.ip.as_str(), l)).collect();
+        report.only_secondary = secondary.iter().filter(|l| !by_ip.contains_key(l.ip.as_str())).cloned().collect();
+
+        self.check_dates(Side::Primary, &primary, &mut report);
+        self.check_dates(Side::Secondary, &secondary, &mut report);
+
+        report
+    }
+}
@@ -0,0 +1,20 @@
+//! Parsers for the lease files of other DHCP servers, into the same [Leases](crate::leases::Leases)
+//! model as the "dhcpd.leases" file. The [LeaseFilterBuilder](crate::util::LeaseFilterBuilder) and
+//! the other tools work the same on their leases.
+//!
+//! * [dnsmasq]: The "dnsmasq.leases" file of dnsmasq
+//! * [kea]: The "kea-leases4.csv" and "kea-leases6.csv" memfiles of ISC Kea, and the conversion
+//!   of the dhcpd leases to "kea-leases4.csv"
+pub mod dnsmasq;
+pub mod kea;
+
+/// The name of the ARP hardware type, as in the "hardware" entry of the dhcpd leases. The types
+/// unknown to dhcpd are kept as the decimal number (i.e., "32" for InfiniBand).
+pub(crate) fn hardware_type(h_type: u8) -> String {
+    match h_type {
+        1 => "ethernet".to_owned(),
+        6 => "token-ring".to_owned(),
+        8 => "fddi".to_owned(),
+        other => other.to_string(),
+    }
+}
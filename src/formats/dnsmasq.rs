@@ -0,0 +1,140 @@
+//! Parser for the "dnsmasq.leases" file.
+//!
+//! Each line of the file is a lease:
+//!
+//! ```text
+//! <expiry> <MAC address> <IP> <hostname or *> <client-id or *>
+//! ```
+//!
+//! Once the server has DHCPv6 leases, a "duid <server DUID>" line is followed by the IPv6 leases,
+//! where the MAC address is replaced with the IAID (prefixed with "T" for temporary addresses).
+//!
+//! The leases map to the [Lease] model as:
+//!
+//! * `expiry` (seconds since the epoch): the "ends" date. An expiry of 0 is a lease that never
+//!   ends, with the "ends" date [Date::NEVER]
+//! * MAC address: the "hardware" entry, of type "ethernet" unless the address is prefixed with
+//!   the ARP hardware type in hexadecimal (i.e., "06-00:11:22:33:44:55" for token ring). IPv6
+//!   leases have no hardware entry
+//! * hostname: the "client-hostname"
+//! * client-id: the "uid". For IPv6, the "uid" identifies the IA as dhcpd does: the IAID (in
+//!   network byte order) followed by the DUID, in hexadecimal (i.e., "00:12:d6:87:00:01:...").
+//!   The "T" prefix of the temporary addresses is not kept
+//!
+//! dnsmasq removes the leases when they expire, so the leases have no binding state.
+//!
+//! ```rust
+//! use dhcpd_parser::formats::dnsmasq;
+//! use dhcpd_parser::leases::LeasesMethods;
+//!
+//! let leases = dnsmasq::parse("1546380000 11:11:11:11:11:11 192.168.0.2 laptop *\n").unwrap().leases;
+//!
+//! assert_eq!(leases[0].ip, "192.168.0.2");
+//! assert_eq!(leases[0].client_hostname.as_deref(), Some("laptop"));
+//! assert_eq!(leases[0].dates.ends.unwrap().to_rfc3339(), "2019-01-01T22:00:00Z");
+//! ```
+use std::fs;
+use std::net::IpAddr;
+use std::path::Path;
+
+use chrono::{DateTime, Utc};
+
+use crate::common::Date;
+use crate::formats::hardware_type;
+use crate::leases::{Hardware, Lease, Leases, LeasesMethods};
+use crate::parser::{decode_bytes, ParserResult};
+
+/// Parse one lease line. `v6` is set once the "duid" line has been read.
+fn parse_line(line: &str, v6: bool) -> Result<Lease, String> {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    if fields.len() != 5 {
+        return Err(format!("Expected 5 fields, found {}", fields.len()));
+    }
+
+    let expiry = fields[0]
+        .parse::<i64>()
+        .map_err(|_| format!("'{}' is not a valid expiry time", fields[0]))?;
+    let ip = fields[2]
+        .parse::<IpAddr>()
+        .map_err(|_| format!("'{}' is not a valid IP address", fields[2]))?;
+    if ip.is_ipv6() != v6 {
+        let expected = if v6 { "IPv6" } else { "IPv4" };
+        return Err(format!("Expected an {} address, found {}", expected, ip));
+    }
+
+    let mut lease = Lease::new();
+    lease.ip = ip.to_string();
+
+    lease.dates.ends = if expiry == 0 {
+        Some(Date::NEVER)
+    } else {
+        let ends = DateTime::<Utc>::from_timestamp(expiry, 0)
+            .ok_or_else(|| format!("'{}' is not a valid expiry time", expiry))?;
+        Some(Date::from_chrono(ends))
+    };
+
+    if v6 {
+        let iaid = fields[1].trim_start_matches('T');
+        let iaid = iaid.parse::<u32>().map_err(|_| format!("'{}' is not a valid IAID", fields[1]))?;
+        let mut uid: Vec<String> = iaid.to_be_bytes().iter().map(|b| format!("{:02x}", b)).collect();
+        if fields[4] != "*" {
+            uid.push(fields[4].to_lowercase());
+        }
+        lease.uid = Some(uid.join(":"));
+    } else {
+        let (h_type, mac) = match fields[1].split_once('-') {
+            Some((h_type, mac)) => {
+                let h_type = u8::from_str_radix(h_type, 16)
+                    .map_err(|_| format!("'{}' is not a valid hardware type", h_type))?;
+                (hardware_type(h_type), mac)
+            }
+            None => ("ethernet".to_owned(), fields[1]),
+        };
+        lease.hardware = Some(Hardware { h_type, mac: mac.to_lowercase() });
+
+        if fields[4] != "*" {
+            lease.uid = Some(fields[4].to_owned());
+        }
+    }
+
+    if fields[3] != "*" {
+        lease.client_hostname = Some(fields[3].to_owned());
+    }
+
+    Ok(lease)
+}
+
+/// Parse the contents of the "dnsmasq.leases" file.
+pub fn parse(input: &str) -> Result<ParserResult, String> {
+    let mut leases = Leases::new();
+    let mut v6 = false;
+
+    for (ndx, line) in input.lines().enumerate() {
+        let line = line.trim();
+
+        if line.is_empty() {
+            continue;
+        }
+
+        if line.starts_with("duid ") {
+            v6 = true;
+            continue;
+        }
+
+        let lease = parse_line(line, v6).map_err(|e| format!("line {}: {}", ndx + 1, e))?;
+        leases.push(lease);
+    }
+
+    Ok(ParserResult::new(leases))
+}
+
+/// Load and parse the "dnsmasq.leases" file at `path` (generally "/var/lib/misc/dnsmasq.leases").
+pub fn parse_file<P: AsRef<Path>>(path: P) -> Result<ParserResult, String> {
+    let path = path.as_ref();
+    let bytes = fs::read(path).map_err(|e| format!("Failed to open: {}. Error: {}", path.display(), e))?;
+
+    let mut res = parse(&decode_bytes(&bytes)).map_err(|e| format!("{}: {}", path.display(), e))?;
+    res.source = Some(path.to_path_buf());
+
+    Ok(res)
+}
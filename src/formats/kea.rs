@@ -0,0 +1,414 @@
+//! Parser for the CSV memfiles of ISC Kea ("kea-leases4.csv" and "kea-leases6.csv").
+//!
+//! The file starts with a header line naming the columns, which tells the DHCPv4 file (with a
+//! "hwaddr" column) from the DHCPv6 file (with a "duid" column). Kea appends a line each time a
+//! lease changes, so a lease can have several lines until the file is cleaned up; as for the
+//! dhcpd leases, the last line for an address is the effective lease (see
+//! [LeaseFilterBuilder::effective](crate::util::LeaseFilterBuilder::effective)).
+//!
+//! The columns map to the [Lease] model as:
+//!
+//! * `expire` (seconds since the epoch): the "ends" date, or [Date::NEVER] for the infinite
+//!   `valid_lifetime` (0xffffffff)
+//! * `expire` minus `valid_lifetime`: the "starts" and "cltt" dates
+//! * `hwaddr`: the "hardware" entry (of the type of the `hwtype` column for DHCPv6)
+//! * `client_id` (DHCPv4) or `duid` (DHCPv6): the "uid"
+//! * `hostname`: the "hostname"
+//! * `state`: the "binding state", see [binding_state]
+//!
+//! The other columns are only in the [KeaLease] records returned by [parse_records].
+//!
+//! The [KeaWriter] converts the dhcpd leases the other way, for the migration to Kea.
+//!
+//! ```rust
+//! use dhcpd_parser::formats::kea;
+//! use dhcpd_parser::leases::LeasesMethods;
+//!
+//! let leases = kea::parse("address,hwaddr,client_id,valid_lifetime,expire,subnet_id,fqdn_fwd,fqdn_rev,hostname,state,user_context
+//! 192.168.0.2,11:11:11:11:11:11,,3600,1546380000,1,0,0,laptop,0,
+//! ").unwrap().leases;
+//!
+//! assert_eq!(leases[0].ip, "192.168.0.2");
+//! assert_eq!(leases[0].binding_state.as_deref(), Some("active"));
+//! assert_eq!(leases[0].dates.starts.unwrap().to_rfc3339(), "2019-01-01T21:00:00Z");
+//! ```
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fs;
+use std::io::{self, Write};
+use std::net::Ipv4Addr;
+use std::path::Path;
+
+use chrono::{DateTime, Utc};
+
+use crate::common::{Date, IpRange};
+use crate::formats::hardware_type;
+use crate::leases::{Hardware, Lease, Leases, LeasesMethods};
+use crate::parser::{decode_bytes, ParserResult};
+use crate::util::LeaseFilterBuilder;
+
+/// A line of the memfile, with the columns that have no equivalent in the [Lease] model.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeaLease {
+    pub lease: Lease,
+    /// The Kea identifier of the subnet of the lease
+    pub subnet_id: u32,
+    /// The lifetime of the lease, in seconds
+    pub valid_lifetime: u32,
+    /// Set if the server updates the forward DNS record
+    pub fqdn_fwd: bool,
+    /// Set if the server updates the reverse DNS record
+    pub fqdn_rev: bool,
+    /// The state code of Kea (see [binding_state])
+    pub state: u32,
+    /// The JSON user context, if any
+    pub user_context: Option<String>,
+    /// The identifier of the pool within the subnet (Kea 2.4 and later)
+    pub pool_id: Option<u32>,
+    /// DHCPv6 only: 0 for an address, 1 for a temporary address and 2 for a prefix
+    pub lease_type: Option<u8>,
+    /// DHCPv6 only: the identity association identifier
+    pub iaid: Option<u32>,
+    /// DHCPv6 only: the length of a delegated prefix (128 for an address)
+    pub prefix_len: Option<u8>,
+}
+
+/// The dhcpd binding state for the state code of Kea:
+///
+/// * 0 (default): "active"
+/// * 1 (declined): "abandoned", as dhcpd does for the addresses declined by a client
+/// * 2 (expired-reclaimed): "free", the lease can be handed out again
+/// * 3 (released): "released"
+/// * 4 (registered): "registered", an address the client assigned itself (DHCPv6)
+///
+/// The other codes are kept as numbers.
+pub fn binding_state(state: u32) -> String {
+    match state {
+        0 => "active".to_owned(),
+        1 => "abandoned".to_owned(),
+        2 => "free".to_owned(),
+        3 => "released".to_owned(),
+        4 => "registered".to_owned(),
+        other => other.to_string(),
+    }
+}
+
+/// Revert the escaping of the commas within the values.
+fn unescape(value: &str) -> String {
+    value.replace("&#x2c", ",")
+}
+
+/// The values of a line, by the name of their column.
+struct Row<'a> {
+    columns: &'a HashMap<&'a str, usize>,
+    values: Vec<&'a str>,
+}
+
+impl<'a> Row<'a> {
+    /// The value of an optional column. Empty values are None.
+    fn get(&self, column: &str) -> Option<&'a str> {
+        self.columns
+            .get(column)
+            .map(|ndx| self.values[*ndx])
+            .filter(|v| !v.is_empty())
+    }
+
+    fn required(&self, column: &str) -> Result<&'a str, String> {
+        self.get(column).ok_or_else(|| format!("Missing value for '{}'", column))
+    }
+
+    fn number<T: std::str::FromStr>(&self, column: &str) -> Result<Option<T>, String> {
+        self.get(column)
+            .map(|v| v.parse::<T>().map_err(|_| format!("'{}' is not a valid {}", v, column)))
+            .transpose()
+    }
+
+    fn flag(&self, column: &str) -> bool {
+        matches!(self.get(column), Some("1") | Some("true"))
+    }
+}
+
+/// The `valid_lifetime` of the leases that never expire.
+const INFINITE_LIFETIME: u32 = u32::MAX;
+
+fn parse_row(row: &Row, v6: bool) -> Result<KeaLease, String> {
+    let mut lease = Lease::new();
+    lease.ip = row.required("address")?.to_owned();
+
+    let expire: i64 = row.number("expire")?.ok_or("Missing value for 'expire'")?;
+    let valid_lifetime: u32 = row.number("valid_lifetime")?.ok_or("Missing value for 'valid_lifetime'")?;
+    let date = |epoch: i64| {
+        DateTime::<Utc>::from_timestamp(epoch, 0)
+            .map(Date::from_chrono)
+            .ok_or_else(|| format!("'{}' is not a valid date", epoch))
+    };
+    lease.dates.ends = Some(if valid_lifetime == INFINITE_LIFETIME { Date::NEVER } else { date(expire)? });
+    lease.dates.starts = Some(date(expire - valid_lifetime as i64)?);
+    lease.dates.cltt = lease.dates.starts;
+
+    if let Some(mac) = row.get("hwaddr") {
+        let h_type = if v6 { hardware_type(row.number("hwtype")?.unwrap_or(1)) } else { "ethernet".to_owned() };
+        lease.hardware = Some(Hardware { h_type, mac: mac.to_lowercase() });
+    }
+
+    lease.uid = row.get(if v6 { "duid" } else { "client_id" }).map(|v| v.to_owned());
+    lease.hostname = row.get("hostname").map(unescape);
+
+    let state = row.number("state")?.unwrap_or(0);
+    lease.binding_state = Some(binding_state(state));
+    lease.abandoned = state == 1;
+
+    Ok(KeaLease {
+        lease,
+        subnet_id: row.number("subnet_id")?.ok_or("Missing value for 'subnet_id'")?,
+        valid_lifetime,
+        fqdn_fwd: row.flag("fqdn_fwd"),
+        fqdn_rev: row.flag("fqdn_rev"),
+        state,
+        user_context: row.get("user_context").map(unescape),
+        pool_id: row.number("pool_id")?,
+        lease_type: row.number("lease_type")?,
+        iaid: row.number("iaid")?,
+        prefix_len: row.number("prefix_len")?,
+    })
+}
+
+/// Parse the contents of a memfile into the [KeaLease] records, keeping all of the columns.
+pub fn parse_records(input: &str) -> Result<Vec<KeaLease>, String> {
+    let mut lines = input.lines().enumerate().filter(|(_, l)| !l.trim().is_empty());
+
+    let header = match lines.next() {
+        Some((_, header)) => header.trim(),
+        None => return Ok(Vec::new()),
+    };
+    let columns: HashMap<&str, usize> = header.split(',').enumerate().map(|(ndx, c)| (c, ndx)).collect();
+
+    let v6 = columns.contains_key("duid");
+    let required: &[&str] = if v6 {
+        &["address", "duid", "valid_lifetime", "expire", "subnet_id"]
+    } else {
+        &["address", "hwaddr", "client_id", "valid_lifetime", "expire", "subnet_id"]
+    };
+    if let Some(missing) = required.iter().find(|c| !columns.contains_key(*c)) {
+        return Err(format!("line 1: Not a Kea memfile, the header has no '{}' column", missing));
+    }
+
+    let mut records = Vec::new();
+    for (ndx, line) in lines {
+        let values: Vec<&str> = line.trim().split(',').collect();
+        if values.len() != columns.len() {
+            return Err(format!("line {}: Expected {} values, found {}", ndx + 1, columns.len(), values.len()));
+        }
+
+        let row = Row { columns: &columns, values };
+        records.push(parse_row(&row, v6).map_err(|e| format!("line {}: {}", ndx + 1, e))?);
+    }
+
+    Ok(records)
+}
+
+/// Parse the contents of a memfile ("kea-leases4.csv" or "kea-leases6.csv").
+pub fn parse(input: &str) -> Result<ParserResult, String> {
+    let mut leases = Leases::new();
+    for record in parse_records(input)? {
+        leases.push(record.lease);
+    }
+
+    Ok(ParserResult::new(leases))
+}
+
+/// Load and parse the memfile at `path` (generally "/var/lib/kea/kea-leases4.csv").
+pub fn parse_file<P: AsRef<Path>>(path: P) -> Result<ParserResult, String> {
+    let path = path.as_ref();
+    let bytes = fs::read(path).map_err(|e| format!("Failed to open: {}. Error: {}", path.display(), e))?;
+
+    let mut res = parse(&decode_bytes(&bytes)).map_err(|e| format!("{}: {}", path.display(), e))?;
+    res.source = Some(path.to_path_buf());
+
+    Ok(res)
+}
+
+/// The header of the "kea-leases4.csv" files written by [KeaWriter], read by Kea 1.7 and later.
+pub const HEADER_V4: &str = "address,hwaddr,client_id,valid_lifetime,expire,subnet_id,fqdn_fwd,fqdn_rev,hostname,state,user_context";
+
+/// The state code of Kea for the dhcpd lease, the inverse of [binding_state]. The leases without a
+/// binding state (BSD) are active unless abandoned.
+///
+/// The "free", "expired", "released" and "backup" states are all "expired-reclaimed" (2), as the
+/// "released" code is not known to most Kea versions. The other states have no code.
+pub fn state_code(lease: &Lease) -> Option<u32> {
+    if lease.abandoned {
+        return Some(1);
+    }
+
+    match lease.binding_state.as_deref() {
+        None | Some("active") => Some(0),
+        Some("abandoned") => Some(1),
+        Some("free") | Some("expired") | Some("released") | Some("backup") => Some(2),
+        Some(_) => None,
+    }
+}
+
+/// The client identifier in the hexadecimal notation of Kea ("01:00:11:22:33:44:55").
+///
+/// dhcpd writes the "uid" as a string with the non-printable bytes as octal escapes
+/// ("\001\000\021\"3DU", with or without the surrounding quotes); the identifiers already in
+/// hexadecimal notation are kept.
+pub fn client_id(uid: &str) -> String {
+    let uid = uid.strip_prefix('"').and_then(|u| u.strip_suffix('"')).unwrap_or(uid);
+    let is_hex = uid.split(':').all(|b| b.len() == 2 && b.chars().all(|c| c.is_ascii_hexdigit()));
+    if is_hex {
+        return uid.to_lowercase();
+    }
+
+    let mut bytes = Vec::new();
+    let mut rest = uid.as_bytes();
+    while let Some((&b, tail)) = rest.split_first() {
+        rest = tail;
+        if b != b'\\' {
+            bytes.push(b);
+            continue;
+        }
+
+        let octal: Vec<u8> = rest.iter().take(3).take_while(|c| (b'0'..=b'7').contains(c)).copied().collect();
+        if octal.len() == 3 {
+            let value = octal.iter().fold(0u32, |acc, c| acc * 8 + (c - b'0') as u32);
+            bytes.push(value as u8);
+            rest = &rest[3..];
+        } else if let Some((&escaped, tail)) = rest.split_first() {
+            bytes.push(escaped);
+            rest = tail;
+        }
+    }
+
+    let hex: Vec<String> = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    hex.join(":")
+}
+
+/// A lease left out by [KeaWriter::write], with the reason.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Unrepresentable {
+    pub lease: Lease,
+    pub reason: String,
+}
+
+/// Writer of the effective dhcpd leases as a Kea "kea-leases4.csv" memfile.
+///
+/// Kea identifies the subnets by number, so the subnet of each lease is found from the ranges
+/// added with [KeaWriter::subnet]. The leases that Kea cannot load are left out and returned by
+/// [KeaWriter::write]:
+///
+/// * The address is not IPv4, or is in none of the subnets
+/// * There is no "ends" date, or no "starts" (or "cltt") date before it
+/// * The binding state has no Kea state code (see [state_code])
+/// * There is no hardware address, except for the abandoned leases
+///
+/// ```rust
+/// use dhcpd_parser::common::IpRange;
+/// use dhcpd_parser::formats::kea::KeaWriter;
+/// use dhcpd_parser::parser;
+///
+/// let leases = parser::parse_file("tests/data/dhcpd-linux.leases").unwrap().leases;
+///
+/// let mut out = Vec::new();
+/// let left_out = KeaWriter::new()
+///     .subnet(IpRange::from("192.168.4.0/24").unwrap(), 1)
+///     .write(&leases, &mut out)
+///     .unwrap();
+///
+/// assert!(left_out.is_empty());
+/// ```
+pub struct KeaWriter {
+    subnets: Vec<(IpRange, u32)>,
+}
+
+impl Default for KeaWriter {
+    fn default() -> Self {
+        KeaWriter::new()
+    }
+}
+
+impl KeaWriter {
+    pub fn new() -> KeaWriter {
+        KeaWriter { subnets: Vec::new() }
+    }
+
+    /// Map the addresses of `range` to the Kea subnet `subnet_id`. The first matching range is used.
+    pub fn subnet(&mut self, range: IpRange, subnet_id: u32) -> &mut Self {
+        self.subnets.push((range, subnet_id));
+        self
+    }
+
+    /// The line of the memfile for the lease, or the reason it cannot be written.
+    fn line(&self, lease: &Lease) -> Result<String, String> {
+        let ip = lease
+            .ip
+            .parse::<Ipv4Addr>()
+            .map_err(|_| format!("'{}' is not an IPv4 address", lease.ip))?;
+        let subnet_id = self
+            .subnets
+            .iter()
+            .find(|(range, _)| range.contains(ip))
+            .map(|(_, id)| *id)
+            .ok_or_else(|| format!("{} is in none of the subnets", ip))?;
+
+        let state = state_code(lease).ok_or_else(|| {
+            format!("The binding state '{}' has no Kea state", lease.binding_state.as_deref().unwrap_or_default())
+        })?;
+
+        let ends = lease.dates.ends.ok_or("There is no ends date")?;
+        let starts = lease
+            .dates
+            .starts
+            .or(lease.dates.cltt)
+            .ok_or("There is no starts date")?
+            .to_chrono();
+        // Kea expires the infinite leases at the start plus the infinite lifetime
+        let (valid_lifetime, ends) = if ends.is_never() {
+            (INFINITE_LIFETIME, starts + chrono::Duration::seconds(INFINITE_LIFETIME as i64))
+        } else {
+            let ends = ends.to_chrono();
+            let valid_lifetime = u32::try_from((ends - starts).num_seconds())
+                .map_err(|_| "The ends date is before the starts date".to_owned())?;
+            (valid_lifetime, ends)
+        };
+
+        let hwaddr = match &lease.hardware {
+            Some(hardware) => hardware.mac.to_lowercase(),
+            None if state == 1 => String::new(),
+            None => return Err("There is no hardware address".to_owned()),
+        };
+
+        let client_id = lease.uid.as_deref().map(client_id).unwrap_or_default();
+        let hostname = lease.hostname.as_ref().or(lease.client_hostname.as_ref());
+
+        Ok(format!(
+            "{},{},{},{},{},{},0,0,{},{},",
+            ip,
+            hwaddr,
+            client_id,
+            valid_lifetime,
+            ends.timestamp(),
+            subnet_id,
+            hostname.map(|h| h.replace(',', "&#x2c")).unwrap_or_default(),
+            state,
+        ))
+    }
+
+    /// Write the memfile with the effective leases. Returns the leases that were left out.
+    pub fn write<W: Write>(&self, leases: &Leases, mut writer: W) -> io::Result<Vec<Unrepresentable>> {
+        let effective = LeaseFilterBuilder::new(leases).effective().collect();
+        let mut left_out = Vec::new();
+
+        writeln!(writer, "{}", HEADER_V4)?;
+        for lease in effective.all() {
+            match self.line(&lease) {
+                Ok(line) => writeln!(writer, "{}", line)?,
+                Err(reason) => left_out.push(Unrepresentable { lease, reason }),
+            }
+        }
+
+        writer.flush()?;
+        Ok(left_out)
+    }
+}
@@ -1,12 +1,15 @@
+use std::borrow::Cow;
 use std::collections::HashSet;
 use std::iter::Peekable;
 use std::ops::Index;
 
 use crate::common::Date;
-use crate::lex::LexItem;
+use crate::lex::{LexItem, Lexer, Token, TokenKind};
+#[cfg(feature = "oui")]
+use crate::oui::OuiDb;
 use chrono::prelude::*;
-
-// TODO: I'm thinking I might write a serialize/deserialize library with "nom" and support serde instead.
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 /// Keywords for in a Lease entry line
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -56,32 +59,38 @@ impl LeaseKeyword {
     }
 
     pub fn from(s: &str) -> Result<LeaseKeyword, String> {
+        LeaseKeyword::lookup(s).ok_or_else(|| format!("'{}' is not a recognized lease option", s))
+    }
+
+    /// Same as [LeaseKeyword::from], without allocating an error for the words that are not keywords.
+    pub(crate) fn lookup(s: &str) -> Option<LeaseKeyword> {
         match s {
-            "abandoned" => Ok(LeaseKeyword::Abandoned),
-            "client-hostname" => Ok(LeaseKeyword::ClientHostname),
-            "ends" => Ok(LeaseKeyword::Ends),
-            "hardware" => Ok(LeaseKeyword::Hardware),
-            "hostname" => Ok(LeaseKeyword::Hostname),
-            "starts" => Ok(LeaseKeyword::Starts),
-            "uid" => Ok(LeaseKeyword::Uid),
-            "binding" => Ok(LeaseKeyword::Binding),
-            "state" => Ok(LeaseKeyword::State),
-            "next" => Ok(LeaseKeyword::Next),
-            "rewind" => Ok(LeaseKeyword::Rewind),
-            "tstp" => Ok(LeaseKeyword::Tstp),
-            "tsfp" => Ok(LeaseKeyword::Tsfp),
-            "atsfp" => Ok(LeaseKeyword::Atsfp),
-            "cltt" => Ok(LeaseKeyword::Cltt),
-            "authoring-byte-order" => Ok(LeaseKeyword::BiteOrder),
+            "abandoned" => Some(LeaseKeyword::Abandoned),
+            "client-hostname" => Some(LeaseKeyword::ClientHostname),
+            "ends" => Some(LeaseKeyword::Ends),
+            "hardware" => Some(LeaseKeyword::Hardware),
+            "hostname" => Some(LeaseKeyword::Hostname),
+            "starts" => Some(LeaseKeyword::Starts),
+            "uid" => Some(LeaseKeyword::Uid),
+            "binding" => Some(LeaseKeyword::Binding),
+            "state" => Some(LeaseKeyword::State),
+            "next" => Some(LeaseKeyword::Next),
+            "rewind" => Some(LeaseKeyword::Rewind),
+            "tstp" => Some(LeaseKeyword::Tstp),
+            "tsfp" => Some(LeaseKeyword::Tsfp),
+            "atsfp" => Some(LeaseKeyword::Atsfp),
+            "cltt" => Some(LeaseKeyword::Cltt),
+            "authoring-byte-order" => Some(LeaseKeyword::BiteOrder),
             // Doubtful we will need support
-            "option" | "set" | "on" | "bootp" | "reserved" | "failover" | "server-duid" => Ok(LeaseKeyword::Ignored),
-            _ => Err(format!("'{}' is not a recognized lease option", s)),
+            "option" | "set" | "on" | "bootp" | "reserved" | "failover" | "server-duid" => Some(LeaseKeyword::Ignored),
+            _ => None,
         }
     }
 }
 
 /// The dates found in the lease entry. The only two that we retain are the "starts" and "ends"
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct LeaseDates {
     /// The lease start DTS
     pub starts: Option<Date>,
@@ -99,6 +108,7 @@ pub struct LeaseDates {
 
 /// The "hardware" entry in the lease
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Hardware {
     /// The type (i.e., "ethernet")
     pub h_type: String,
@@ -106,6 +116,14 @@ pub struct Hardware {
     pub mac: String,
 }
 
+#[cfg(feature = "oui")]
+impl Hardware {
+    /// The vendor of the MAC address in the IEEE registry (requires the "oui" feature).
+    pub fn vendor<'a>(&self, db: &'a OuiDb) -> Option<&'a str> {
+        db.vendor(&self.mac)
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum LeasesField {
     ClientHostname,
@@ -133,6 +151,7 @@ impl LeasesField {
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Leases(Vec<Lease>);
 
 impl Index<usize> for Leases {
@@ -361,6 +380,7 @@ impl LeasesMethods for Leases {
 
 /// A lease entry from the dhcpd.leases file, and contained within a [Leases] instance.
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Lease {
     /// The IP associated with the lease entry.
     pub ip: String,
@@ -428,7 +448,8 @@ impl Lease {
         return true;
     }
 
-    /// Helper method to give an indication if the loaded leases file is for Linux
+    /// Helper method to give an indication if the loaded leases file is for Linux. To know the
+    /// dialect before parsing, see [detect_format](crate::parser::detect_format).
     pub fn is_linux(&self) -> bool {
         return match self.binding_state {
             Some(_) => true,
@@ -441,9 +462,10 @@ impl Lease {
         return self.hardware.as_ref().unwrap().mac.to_owned()
     }
 
-    /// Helper to get the "ends" date as a [chrono::DateTime]
+    /// Helper to get the "ends" date as a [chrono::DateTime]: the last second of the year 9999
+    /// for a lease that never ends ([Date::NEVER]), or the Unix epoch if there is no "ends" date.
     pub fn lease_end_dts(&self) -> DateTime<Utc> {
-        return self.dates.ends.unwrap().to_chrono()
+        return self.dates.ends.unwrap_or_else(Date::new).to_chrono()
     }
 
     /// Indicates if the lease is currently active (true), or expired (false)
@@ -483,35 +505,164 @@ impl Lease {
 
         false
     }
+
+    /// Indicates if the lease is bound to its client at the instant `dt`: it is not abandoned,
+    /// the binding state (if any) is "active", and `dt` is within the "starts" and "ends" dates.
+    /// Unlike [is_active](Self::is_active), this allows evaluating the leases at any instant.
+    pub fn is_bound_at(&self, dt: DateTime<Utc>) -> bool {
+        if self.abandoned {
+            return false;
+        }
+
+        if let Some(binding_state) = &self.binding_state {
+            if binding_state != "active" {
+                return false;
+            }
+        }
+
+        if let Some(starts) = self.dates.starts {
+            if starts.to_chrono() > dt {
+                return false;
+            }
+        }
+
+        self.active_after(dt)
+    }
+}
+
+/// The "hardware" entry in a [LeaseRef], borrowed from the input.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HardwareRef<'a> {
+    /// The type (i.e., "ethernet")
+    pub h_type: &'a str,
+    /// The MAC address
+    pub mac: &'a str,
+}
+
+/// A read-only lease entry borrowing its values from the contents of the leases file.
+///
+/// This is the zero-copy counterpart of [Lease], returned by [parse_ref](crate::parser::parse_ref).
+/// The hostnames are only copied when they contain quotes other than the surrounding ones (which
+/// have to be removed, as for [Lease]). Use [LeaseRef::to_lease] to get an owned [Lease].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LeaseRef<'a> {
+    /// The IP associated with the lease entry.
+    pub ip: &'a str,
+    /// The dates found in the lease entry.
+    pub dates: LeaseDates,
+    /// The "hardware" entry in the lease.
+    pub hardware: Option<HardwareRef<'a>>,
+    /// The client identifier used by the client to acquire the lease.
+    pub uid: Option<&'a str>,
+    /// The hostname, if the client sends the Client Hostname option.
+    pub client_hostname: Option<Cow<'a, str>>,
+    /// The hostname, if the client sends the Hostname option.
+    pub hostname: Option<Cow<'a, str>>,
+    /// Flag to indicate the server has abandoned the lease due to a detected conflict.
+    pub abandoned: bool,
+    /// The "binding state" (_Linux only_)
+    pub binding_state: Option<&'a str>,
+    /// The "next binding state" (_Linux only_)
+    pub next_binding_state: Option<&'a str>,
+    /// The "rewind binding state" (_Linux only_)
+    pub rewind_binding_state: Option<&'a str>,
+    /// Linux only: Contains the value from "authoring-byte-order"
+    pub byte_order: Option<&'a str>,
+}
+
+impl<'a> LeaseRef<'a> {
+    /// Create a new instance for the IP with defaults.
+    pub fn new(ip: &'a str) -> LeaseRef<'a> {
+        LeaseRef {
+            ip,
+            dates: Lease::new().dates,
+            hardware: None,
+            uid: None,
+            client_hostname: None,
+            hostname: None,
+            abandoned: false,
+            binding_state: None,
+            next_binding_state: None,
+            rewind_binding_state: None,
+            byte_order: None,
+        }
+    }
+
+    /// Copy the borrowed values into an owned [Lease].
+    pub fn to_lease(&self) -> Lease {
+        Lease {
+            ip: self.ip.to_owned(),
+            dates: self.dates.clone(),
+            hardware: self.hardware.as_ref().map(|h| Hardware {
+                h_type: h.h_type.to_owned(),
+                mac: h.mac.to_owned(),
+            }),
+            uid: self.uid.map(str::to_owned),
+            client_hostname: self.client_hostname.as_ref().map(|h| h.to_string()),
+            hostname: self.hostname.as_ref().map(|h| h.to_string()),
+            abandoned: self.abandoned,
+            binding_state: self.binding_state.map(str::to_owned),
+            next_binding_state: self.next_binding_state.map(str::to_owned),
+            rewind_binding_state: self.rewind_binding_state.map(str::to_owned),
+            byte_order: self.byte_order.map(str::to_owned),
+        }
+    }
 }
 
-fn parse_date<'l, T: Iterator<Item = &'l LexItem>>(iter: &mut Peekable<T>) -> Result<Date, String> {
+fn parse_date<'l, T: Iterator<Item = &'l LexItem>>(iter: &mut Peekable<T>) -> Result<Option<Date>, String> {
     iter.next();
-    let weekday = iter
-        .peek()
-        .expect("Weekday for end date expected")
-        .to_string();
+    let weekday = peek_text(iter, "Weekday")?;
     iter.next();
-    let date = iter.peek().expect("Date for end date expected").to_string();
+
+    // ISC style dates: "never" and "epoch <seconds>"
+    match weekday.as_str() {
+        "never" => return Ok(Some(Date::NEVER)),
+        "epoch" => {
+            let seconds = peek_text(iter, "Seconds")?;
+            iter.next();
+            return epoch_date(&seconds).map(Some);
+        }
+        _ => (),
+    }
+
+    let date = peek_text(iter, "Date")?;
     iter.next();
-    let time = iter.peek().expect("Time for end date expected").to_string();
+    let time = peek_text(iter, "Time")?;
     iter.next();
 
     // Consume the next token if it's "UTC" (BSD style date)
     iter.next_if(|&k| String::from("UTC") == k.to_string());
 
-    Date::from(weekday, date, time)
+    Date::from(weekday, date, time).map(Some)
+}
+
+/// The date of an "epoch <seconds>" value.
+fn epoch_date(seconds: &str) -> Result<Date, String> {
+    seconds
+        .parse::<i64>()
+        .ok()
+        .and_then(|s| DateTime::<Utc>::from_timestamp(s, 0))
+        .map(Date::from_chrono)
+        .ok_or_else(|| format!("'{}' is not a valid epoch date", seconds))
+}
+
+/// The text of the next token, without consuming it.
+fn peek_text<'l, T: Iterator<Item = &'l LexItem>>(iter: &mut Peekable<T>, what: &str) -> Result<String, String> {
+    match iter.peek() {
+        Some(token) => Ok(token.to_string()),
+        None => Err(format!("{} expected. Found end of input", what)),
+    }
 }
 
 /// Check to see if the next token is an endl.
 fn expect_endl<'l, T: Iterator<Item = &'l LexItem>>(iter: &mut Peekable<T>) -> Result<(), String> {
-    return match iter.peek().unwrap() {
-        LexItem::Endl => Ok(()),
-        s => Err(format!("Expected semicolon, found {}", s.to_string())),
+    return match iter.peek() {
+        Some(LexItem::Endl) => Ok(()),
+        Some(s) => Err(format!("Expected semicolon, found {}", s.to_string())),
+        None => Err("Expected semicolon, found end of input".to_owned()),
     }
 }
 
-// TODO: Remove all the calls to ".expect()" - they cause wide-spread panic. Replace with errors.
 pub fn parse_lease<'l, T: Iterator<Item = &'l LexItem>>(
     lease: &mut Lease,
     iter: &mut Peekable<T>,
@@ -519,34 +670,33 @@ pub fn parse_lease<'l, T: Iterator<Item = &'l LexItem>>(
     while let Some(&nc) = iter.peek() {
         match nc {
             LexItem::Opt(LeaseKeyword::Starts) => {
-                let dt = parse_date(iter)?;
-                lease.dates.starts.replace(dt);
+                lease.dates.starts = parse_date(iter)?;
             }
             LexItem::Opt(LeaseKeyword::Ends) => {
-                lease.dates.ends.replace(parse_date(iter)?);
+                lease.dates.ends = parse_date(iter)?;
                 expect_endl(iter)?
             }
             LexItem::Opt(LeaseKeyword::Tstp) => {
-                lease.dates.tstp.replace(parse_date(iter)?);
+                lease.dates.tstp = parse_date(iter)?;
                 expect_endl(iter)?
             }
             LexItem::Opt(LeaseKeyword::Cltt) => {
-                lease.dates.cltt.replace(parse_date(iter)?);
+                lease.dates.cltt = parse_date(iter)?;
                 expect_endl(iter)?
             }
             LexItem::Opt(LeaseKeyword::Tsfp) => {
-                lease.dates.tsfp.replace(parse_date(iter)?);
+                lease.dates.tsfp = parse_date(iter)?;
                 expect_endl(iter)?
             }
             LexItem::Opt(LeaseKeyword::Atsfp) => {
-                lease.dates.atsfp.replace(parse_date(iter)?);
+                lease.dates.atsfp = parse_date(iter)?;
                 expect_endl(iter)?
             }
             LexItem::Opt(LeaseKeyword::Hardware) => {
                 iter.next();
-                let h_type = iter.peek().expect("Hardware type expected").to_string();
+                let h_type = peek_text(iter, "Hardware type")?;
                 iter.next();
-                let mac = iter.peek().expect("MAC address expected").to_string();
+                let mac = peek_text(iter, "MAC address")?;
                 iter.next();
                 expect_endl(iter)?;
 
@@ -557,27 +707,21 @@ pub fn parse_lease<'l, T: Iterator<Item = &'l LexItem>>(
             }
             LexItem::Opt(LeaseKeyword::Uid) => {
                 iter.next();
-                lease
-                    .uid
-                    .replace(iter.peek().expect("Client identifier expected").to_string());
+                lease.uid.replace(peek_text(iter, "Client identifier")?);
 
                 iter.next();
                 expect_endl(iter)?
             }
             LexItem::Opt(LeaseKeyword::ClientHostname) => {
                 iter.next();
-                lease.client_hostname.replace(unquote_value(
-                    iter.peek().expect("Client hostname expected").to_string(),
-                ));
+                lease.client_hostname.replace(unquote_value(peek_text(iter, "Client hostname")?));
 
                 iter.next();
                 expect_endl(iter)?
             }
             LexItem::Opt(LeaseKeyword::Hostname) => {
                 iter.next();
-                lease.hostname.replace(unquote_value(
-                    iter.peek().expect("Hostname expected").to_string(),
-                ));
+                lease.hostname.replace(unquote_value(peek_text(iter, "Hostname")?));
 
                 iter.next();
                 expect_endl(iter)?
@@ -590,7 +734,7 @@ pub fn parse_lease<'l, T: Iterator<Item = &'l LexItem>>(
             LexItem::Opt(LeaseKeyword::Binding) => {
                 iter.next();
                 if Option::None == iter.next_if(|&k| String::from("state") == k.to_string()) {
-                    return Err(format!("Expected kw 'state'. Found {}", iter.peek().unwrap().to_string()))
+                    return Err(format!("Expected kw 'state'. Found {}", peek_text(iter, "Kw 'state'")?))
                 }
                 match iter.next_if(|&k | k != &LexItem::Endl) {
                     Some(state) => lease.binding_state.replace(state.to_string()),
@@ -601,10 +745,10 @@ pub fn parse_lease<'l, T: Iterator<Item = &'l LexItem>>(
             LexItem::Opt(LeaseKeyword::Next) => {
                 iter.next();
                 if Option::None == iter.next_if(|&k| String::from("binding") == k.to_string()) {
-                    return Err(format!("Expected kw 'binding'. Found {}", iter.peek().unwrap().to_string()))
+                    return Err(format!("Expected kw 'binding'. Found {}", peek_text(iter, "Kw 'binding'")?))
                 }
                 if Option::None == iter.next_if(|&k| String::from("state") == k.to_string()) {
-                    return Err(format!("Expected kw 'state'. Found {}", iter.peek().unwrap().to_string()))
+                    return Err(format!("Expected kw 'state'. Found {}", peek_text(iter, "Kw 'state'")?))
                 }
                 match iter.next_if(|&k | k != &LexItem::Endl) {
                     Some(state) => lease.next_binding_state.replace(state.to_string()),
@@ -615,10 +759,10 @@ pub fn parse_lease<'l, T: Iterator<Item = &'l LexItem>>(
             LexItem::Opt(LeaseKeyword::Rewind) => {
                 iter.next();
                 if Option::None == iter.next_if(|&k| String::from("binding") == k.to_string()) {
-                    return Err(format!("Expected kw 'binding'. Found {}", iter.peek().unwrap().to_string()))
+                    return Err(format!("Expected kw 'binding'. Found {}", peek_text(iter, "Kw 'binding'")?))
                 }
                 if Option::None == iter.next_if(|&k| String::from("state") == k.to_string()) {
-                    return Err(format!("Expected kw 'state'. Found {}", iter.peek().unwrap().to_string()))
+                    return Err(format!("Expected kw 'state'. Found {}", peek_text(iter, "Kw 'state'")?))
                 }
                 match iter.next_if(|&k | k != &LexItem::Endl) {
                     Some(state) => lease.rewind_binding_state.replace(state.to_string()),
@@ -659,3 +803,133 @@ pub fn parse_lease<'l, T: Iterator<Item = &'l LexItem>>(
 fn unquote_value(v: String) -> String {
     v.replace("\"", "")
 }
+
+/// Borrowed counterpart of [unquote_value]. Only allocates if there are quotes within the value.
+fn unquote_ref(v: &str) -> Cow<'_, str> {
+    let trimmed = v.strip_prefix('"').and_then(|v| v.strip_suffix('"')).unwrap_or(v);
+
+    if trimmed.contains('"') {
+        Cow::Owned(v.replace("\"", ""))
+    } else {
+        Cow::Borrowed(trimmed)
+    }
+}
+
+/// Get the text of the next token, which is expected to be a value for `what`.
+pub(crate) fn next_text<'a>(iter: &mut Peekable<Lexer<'a>>, what: &str) -> Result<&'a str, String> {
+    match iter.next() {
+        Some(token) => Ok(token.text),
+        None => Err(format!("{} expected. Found end of input", what)),
+    }
+}
+
+/// Consume the expected keyword `word`.
+fn expect_word<'a>(iter: &mut Peekable<Lexer<'a>>, word: &str) -> Result<(), String> {
+    match iter.next() {
+        Some(token) if token.text == word => Ok(()),
+        Some(token) => Err(format!("Expected kw '{}'. Found {}", word, token.text)),
+        None => Err(format!("Expected kw '{}'. Found end of input", word)),
+    }
+}
+
+/// Consume the endl ';' terminating the statement.
+fn expect_endl_ref<'a>(iter: &mut Peekable<Lexer<'a>>) -> Result<(), String> {
+    match iter.next() {
+        Some(Token { kind: TokenKind::Endl, .. }) => Ok(()),
+        Some(token) => Err(format!("Expected semicolon, found {}", token.text)),
+        None => Err("Expected semicolon, found end of input".to_owned()),
+    }
+}
+
+fn parse_date_ref<'a>(iter: &mut Peekable<Lexer<'a>>) -> Result<Option<Date>, String> {
+    let weekday = next_text(iter, "Weekday")?;
+
+    // ISC style dates: "never" and "epoch <seconds>"
+    let dt = match weekday {
+        "never" => Some(Date::NEVER),
+        "epoch" => Some(epoch_date(next_text(iter, "Seconds")?)?),
+        _ => {
+            let date = next_text(iter, "Date")?;
+            let time = next_text(iter, "Time")?;
+
+            // Consume the next token if it's "UTC" (BSD style date)
+            iter.next_if(|k| k.text == "UTC");
+
+            Some(Date::from_strs(weekday, date, time)?)
+        }
+    };
+
+    expect_endl_ref(iter)?;
+    Ok(dt)
+}
+
+/// Parse the statements of a lease, up to and including the closing '}', from the zero-copy lexer.
+/// This is the borrowed counterpart of [parse_lease].
+pub(crate) fn parse_lease_ref<'a>(
+    lease: &mut LeaseRef<'a>,
+    iter: &mut Peekable<Lexer<'a>>,
+) -> Result<(), String> {
+    while let Some(token) = iter.next() {
+        match token.kind {
+            TokenKind::Opt(LeaseKeyword::Starts) => lease.dates.starts = parse_date_ref(iter)?,
+            TokenKind::Opt(LeaseKeyword::Ends) => lease.dates.ends = parse_date_ref(iter)?,
+            TokenKind::Opt(LeaseKeyword::Tstp) => lease.dates.tstp = parse_date_ref(iter)?,
+            TokenKind::Opt(LeaseKeyword::Cltt) => lease.dates.cltt = parse_date_ref(iter)?,
+            TokenKind::Opt(LeaseKeyword::Tsfp) => lease.dates.tsfp = parse_date_ref(iter)?,
+            TokenKind::Opt(LeaseKeyword::Atsfp) => lease.dates.atsfp = parse_date_ref(iter)?,
+            TokenKind::Opt(LeaseKeyword::Hardware) => {
+                let h_type = next_text(iter, "Hardware type")?;
+                let mac = next_text(iter, "MAC address")?;
+                expect_endl_ref(iter)?;
+
+                lease.hardware = Some(HardwareRef { h_type, mac });
+            }
+            TokenKind::Opt(LeaseKeyword::Uid) => {
+                lease.uid = Some(next_text(iter, "Client identifier")?);
+                expect_endl_ref(iter)?
+            }
+            TokenKind::Opt(LeaseKeyword::ClientHostname) => {
+                lease.client_hostname = Some(unquote_ref(next_text(iter, "Client hostname")?));
+                expect_endl_ref(iter)?
+            }
+            TokenKind::Opt(LeaseKeyword::Hostname) => {
+                lease.hostname = Some(unquote_ref(next_text(iter, "Hostname")?));
+                expect_endl_ref(iter)?
+            }
+            TokenKind::Opt(LeaseKeyword::Abandoned) => {
+                lease.abandoned = true;
+                expect_endl_ref(iter)?
+            }
+            TokenKind::Opt(LeaseKeyword::Binding) => {
+                expect_word(iter, "state")?;
+                lease.binding_state = Some(next_text(iter, "Binding state")?);
+                expect_endl_ref(iter)?
+            }
+            TokenKind::Opt(LeaseKeyword::Next) => {
+                expect_word(iter, "binding")?;
+                expect_word(iter, "state")?;
+                lease.next_binding_state = Some(next_text(iter, "Next binding state")?);
+                expect_endl_ref(iter)?
+            }
+            TokenKind::Opt(LeaseKeyword::Rewind) => {
+                expect_word(iter, "binding")?;
+                expect_word(iter, "state")?;
+                lease.rewind_binding_state = Some(next_text(iter, "Rewind binding state")?);
+                expect_endl_ref(iter)?
+            }
+            TokenKind::Paren('}') => {
+                return Ok(());
+            }
+            TokenKind::Opt(LeaseKeyword::Ignored) => {
+                // Consume up to the endl
+                while iter.next_if(|k| k.kind != TokenKind::Endl && k.kind != TokenKind::Paren('}')).is_some() {}
+                expect_endl_ref(iter)?
+            }
+            _ => {
+                return Err(format!("Unexpected option '{}'", token.text));
+            }
+        }
+    }
+
+    Err("Expected end of section with '}', found end of input".to_owned())
+}
\ No newline at end of file
@@ -1,5 +1,8 @@
+//! Tokenizer for the contents of the leases and configuration files.
+//!
+//! * [Lexer]: Zero-copy iterator over the [Token] items of a borrowed `&str`
+//! * [lex]: Collects the tokens as owned [LexItem] items
 use std::fmt;
-use std::iter::Peekable;
 
 use crate::leases::LeaseKeyword;
 use crate::parser::ConfigKeyword;
@@ -25,79 +28,161 @@ impl fmt::Display for LexItem {
     }
 }
 
-pub fn lex<S>(input: S) -> Result<Vec<LexItem>, String>
-where
-    S: Into<String>,
-{
-    let mut result = Vec::new();
-    let input_str = input.into();
-    let mut it = input_str.chars().peekable();
+impl<'a> From<Token<'a>> for LexItem {
+    fn from(token: Token<'a>) -> LexItem {
+        match token.kind {
+            TokenKind::Paren(c) => LexItem::Paren(c),
+            TokenKind::Endl => LexItem::Endl,
+            TokenKind::Word => LexItem::Word(token.text.to_owned()),
+            TokenKind::Opt(kw) => LexItem::Opt(kw),
+            TokenKind::Decl(kw) => LexItem::Decl(kw),
+        }
+    }
+}
 
-    while let Some(&c) = it.peek() {
-        match c {
-            '(' | ')' | '[' | ']' | '{' | '}' => {
-                result.push(LexItem::Paren(c));
-                it.next();
-            }
-            '#'  => {
-                consume_comment(&mut it);
-            }
-            ' ' | '\n' | '\t' => {
-                it.next();
-            }
-            ';' => {
-                result.push(LexItem::Endl);
-                it.next();
-            }
-            _ => {
-                let w = get_word(&mut it);
-
-                // Is this the keyword "lease"?
-                let kw = ConfigKeyword::from(&w);
-                if kw.is_ok() {
-                    result.push(LexItem::Decl(kw.unwrap()));
-                } else {
-                    // Is this one of the other valid `LeaseKeyword` words?
-                    let kw = LeaseKeyword::from(&w);
-                    if kw.is_ok() {
-                        result.push(LexItem::Opt(kw.unwrap()));
-                    } else {
-                        result.push(LexItem::Word(w));
-                    }
-                }
-            }
+/// The kind of a [Token]. Mirrors the variants of [LexItem], without owning the word.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TokenKind {
+    Paren(char),
+    Endl,
+    Word,
+    Opt(LeaseKeyword),
+    Decl(ConfigKeyword),
+}
+
+/// Byte offsets of a [Token] within the input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    /// Offset of the first byte of the token.
+    pub start: usize,
+    /// Offset one past the last byte of the token.
+    pub end: usize,
+}
+
+/// A token borrowed from the input of the [Lexer].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token<'a> {
+    pub kind: TokenKind,
+    /// The slice of the input for the token.
+    pub text: &'a str,
+    /// The location of `text` within the input.
+    pub span: Span,
+}
+
+/// Zero-copy lexer over a borrowed `&str`.
+///
+/// The lexer walks the bytes of the input and yields each [Token] as a slice of the input, so
+/// no allocation is made per word. All the separators (whitespace, ';', '#' and the parens) are
+/// ASCII, so every slice falls on a UTF-8 boundary.
+///
+/// ```rust
+/// use dhcpd_parser::lex::{Lexer, TokenKind};
+///
+/// let tokens: Vec<_> = Lexer::new("lease 192.168.0.2 { abandoned; }").collect();
+///
+/// assert_eq!(tokens.len(), 6);
+/// assert_eq!(tokens[1].text, "192.168.0.2");
+/// assert_eq!(tokens[1].kind, TokenKind::Word);
+/// assert_eq!((tokens[1].span.start, tokens[1].span.end), (6, 17));
+/// ```
+pub struct Lexer<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl<'a> Lexer<'a> {
+    pub fn new(input: &'a str) -> Lexer<'a> {
+        Lexer { input, pos: 0 }
+    }
+
+    fn token(&mut self, kind: TokenKind, start: usize, end: usize) -> Token<'a> {
+        self.pos = end;
+        Token {
+            kind,
+            text: &self.input[start..end],
+            span: Span { start, end },
         }
     }
-    Ok(result)
 }
 
-/// Get the next word up to either whitespace or a line terminator ';"
-fn get_word<T: Iterator<Item = char>>(iter: &mut Peekable<T>) -> String {
-    let mut word = String::new();
+impl<'a> Iterator for Lexer<'a> {
+    type Item = Token<'a>;
+
+    fn next(&mut self) -> Option<Token<'a>> {
+        let bytes = self.input.as_bytes();
+
+        while self.pos < bytes.len() {
+            let start = self.pos;
+
+            match bytes[start] {
+                c @ (b'(' | b')' | b'[' | b']' | b'{' | b'}') => {
+                    return Some(self.token(TokenKind::Paren(c as char), start, start + 1));
+                }
+                b'#' => {
+                    // Skip until we are sitting on a '\n' or EOF
+                    self.pos = bytes[start..]
+                        .iter()
+                        .position(|&b| b == b'\n')
+                        .map_or(bytes.len(), |n| start + n);
+                }
+                b';' => {
+                    return Some(self.token(TokenKind::Endl, start, start + 1));
+                }
+                c if c.is_ascii_whitespace() => {
+                    self.pos += 1;
+                }
+                _ => {
+                    // A quoted string is a single word, even with whitespace or ';' in it
+                    let from = match bytes[start] {
+                        b'"' => closing_quote(&bytes[start..]).map_or(start, |n| start + n),
+                        _ => start,
+                    };
+
+                    // Get the word up to either whitespace or a line terminator ';'
+                    let end = bytes[from..]
+                        .iter()
+                        .position(|&b| b.is_ascii_whitespace() || b == b';')
+                        .map_or(bytes.len(), |n| from + n);
+                    let word = &self.input[start..end];
+
+                    // Is this the keyword "lease", or one of the other valid `LeaseKeyword` words?
+                    let kind = match ConfigKeyword::lookup(word) {
+                        Some(kw) => TokenKind::Decl(kw),
+                        None => match LeaseKeyword::lookup(word) {
+                            Some(kw) => TokenKind::Opt(kw),
+                            None => TokenKind::Word,
+                        },
+                    };
 
-    while let Some(&nc) = iter.peek() {
-        if nc.is_whitespace() || nc == ';' {
-            break;
+                    return Some(self.token(kind, start, end));
+                }
+            }
         }
 
-        word.push(nc);
-        iter.next();
+        None
     }
-    word
 }
 
-/// Advance iterator past comment. The iterator will be sitting either on a '\n' or EOF.
-fn consume_comment<T: Iterator<Item = char>>(iter: &mut Peekable<T>) -> () {
-    // Advance past the '#' we peeked at
-    iter.next();
+/// Offset of the quote closing the string at the start of `bytes`, skipping the escaped characters.
+fn closing_quote(bytes: &[u8]) -> Option<usize> {
+    let mut ndx = 1;
+
+    while ndx < bytes.len() {
+        match bytes[ndx] {
+            b'\\' => ndx += 2,
+            b'"' => return Some(ndx),
+            _ => ndx += 1,
+        }
+    }
+
+    None
+}
 
-    // Skip until we peek a '\n'
-    // while let Some(&nc) = iter.peek() {
-    //     match iter.next_if(|&k | k != '\n') {
-    //         Some(state) => (),
-    //         None => break
-    //     };
-    // }
+pub fn lex<S>(input: S) -> Result<Vec<LexItem>, String>
+where
+    S: Into<String>,
+{
+    let input_str = input.into();
 
-    while let Some(_nc) = iter.next_if(|&k | k != '\n') {}
+    Ok(Lexer::new(&input_str).map(LexItem::from).collect())
 }
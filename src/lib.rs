@@ -5,6 +5,8 @@
 //! to be complete and exhaustive per either the Linux or ISC specifications. It should be sufficient
 //! for reading the contents of a leases file.
 //!
+//! The [config] module parses the "dhcpd.conf" configuration file with the same tokenizer.
+//!
 //! # Usage
 //!
 //! Add the library to your project's Cargo.toml (as usual). Refer the examples for more details.
@@ -58,10 +60,27 @@
 //! * [DHCPD Wikipedia](https://en.wikipedia.org/wiki/DHCPD)
 //! * [ISC DHCP](https://www.isc.org/dhcp/)
 
+pub mod client;
 pub mod common;
+pub mod config;
+pub mod correlate;
+pub mod dns;
+pub mod export;
+pub mod failover;
+pub mod formats;
 pub mod leases;
+pub mod metrics;
+pub mod neighbors;
+#[cfg(feature = "oui")]
+pub mod oui;
 pub mod parser;
+#[cfg(feature = "redact")]
+pub mod redact;
+pub mod renewal;
+pub mod store;
 
-mod lex;
+pub mod lex;
 // TODO: Wrap this as an optional feature
 pub mod util;
+#[cfg(feature = "tokio")]
+pub mod watch;
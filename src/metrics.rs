@@ -0,0 +1,226 @@
+//! Rendering of the lease state as [OpenMetrics](https://openmetrics.io) text, for scraping by
+//! Prometheus.
+//!
+//! The metrics are computed from the "effective" leases (the last entry for each IP, see
+//! [LeaseFilterBuilder::effective]), grouped per pool. The pools are either configured with
+//! [MetricsBuilder::pool], or the subnets of [MetricsBuilder::subnet_prefix] bits.
+//!
+//! ```rust
+//! use dhcpd_parser::metrics::MetricsBuilder;
+//! use dhcpd_parser::parser;
+//!
+//! let leases = parser::parse_file("tests/data/dhcpd-linux.leases").unwrap().leases;
+//! let text = MetricsBuilder::new().render(&leases);
+//!
+//! assert!(text.contains("dhcpd_leases{pool=\"192.168.4.0/24\",state=\"free\"} 5\n"));
+//! assert!(text.ends_with("# EOF\n"));
+//! ```
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::Write;
+use std::net::Ipv4Addr;
+use std::time::SystemTime;
+
+use chrono::prelude::*;
+
+use crate::common::IpRange;
+use crate::leases::{Lease, Leases, LeasesMethods};
+use crate::util::LeaseFilterBuilder;
+
+/// The states of the leases counted by the "dhcpd_leases" metric.
+pub const STATES: [&str; 5] = ["active", "free", "expired", "abandoned", "other"];
+
+/// The default buckets, in seconds, of the time-to-expiry histogram.
+pub const DEFAULT_BUCKETS: [u64; 9] = [60, 300, 900, 1800, 3600, 7200, 14400, 43200, 86400];
+
+/// The state of the lease at the instant `at`, as one of [STATES].
+pub fn lease_state(lease: &Lease, at: DateTime<Utc>) -> &'static str {
+    if lease.abandoned || lease.binding_state.as_deref() == Some("abandoned") {
+        return "abandoned";
+    }
+
+    if lease.is_bound_at(at) {
+        return "active";
+    }
+
+    match lease.binding_state.as_deref() {
+        Some("free") | Some("backup") | Some("released") => "free",
+        // The lease ran out without the server rewriting it (always the case for BSD)
+        Some("active") | Some("expired") | None => "expired",
+        Some(_) => "other",
+    }
+}
+
+/// Builder for the OpenMetrics text of the [Leases].
+///
+/// Like the [LeaseFilterBuilder], the settings are supplied with chained methods before the
+/// final call to [render](MetricsBuilder::render).
+pub struct MetricsBuilder {
+    pools: Vec<(String, IpRange)>,
+    subnet_prefix: u32,
+    at: Option<DateTime<Utc>>,
+    buckets: Vec<u64>,
+    parse_errors: u64,
+    file_modified: Option<SystemTime>,
+}
+
+impl Default for MetricsBuilder {
+    fn default() -> Self {
+        MetricsBuilder::new()
+    }
+}
+
+impl MetricsBuilder {
+    pub fn new() -> MetricsBuilder {
+        MetricsBuilder {
+            pools: Vec::new(),
+            subnet_prefix: 24,
+            at: None,
+            buckets: DEFAULT_BUCKETS.to_vec(),
+            parse_errors: 0,
+            file_modified: None,
+        }
+    }
+
+    /// Add a pool to group the leases by. A lease is counted in the first pool containing its IP;
+    /// the leases outside all of the pools are counted in the "unassigned" pool. If no pool is
+    /// added, the leases are grouped by subnet instead.
+    pub fn pool(&mut self, name: &str, range: IpRange) -> &mut Self {
+        self.pools.push((name.to_owned(), range));
+        self
+    }
+
+    /// The prefix length of the subnets used to group the leases when no pool is added (24 by default).
+    pub fn subnet_prefix(&mut self, prefix: u32) -> &mut Self {
+        self.subnet_prefix = prefix.min(32);
+        self
+    }
+
+    /// Evaluate the leases at the instant `dt` rather than [Utc::now()].
+    pub fn at(&mut self, dt: DateTime<Utc>) -> &mut Self {
+        self.at = Some(dt);
+        self
+    }
+
+    /// The upper bounds, in seconds, of the buckets of the time-to-expiry histogram.
+    pub fn buckets(&mut self, buckets: &[u64]) -> &mut Self {
+        self.buckets = buckets.to_vec();
+        self.buckets.sort_unstable();
+        self
+    }
+
+    /// The number of times the leases file failed to parse, reported by the caller.
+    pub fn parse_errors(&mut self, count: u64) -> &mut Self {
+        self.parse_errors = count;
+        self
+    }
+
+    /// The modification time of the leases file, to report its age.
+    pub fn file_modified(&mut self, modified: SystemTime) -> &mut Self {
+        self.file_modified = Some(modified);
+        self
+    }
+
+    fn pool_of(&self, ip: &str) -> String {
+        let ip = match ip.parse::<Ipv4Addr>() {
+            Ok(ip) => ip,
+            Err(_) => return "unknown".to_owned(),
+        };
+
+        if self.pools.is_empty() {
+            return IpRange::subnet(ip, self.subnet_prefix).to_string();
+        }
+
+        self.pools
+            .iter()
+            .find(|(_, range)| range.contains(ip))
+            .map_or_else(|| "unassigned".to_owned(), |(name, _)| name.clone())
+    }
+
+    /// Render the metrics of the leases as OpenMetrics text.
+    pub fn render(&self, leases: &Leases) -> String {
+        let at = self.at.unwrap_or_else(Utc::now);
+        let effective = LeaseFilterBuilder::new(leases).effective().collect();
+
+        // Keep the configured pools in their order, even when empty
+        let mut counts: BTreeMap<(usize, String), [u64; 5]> = BTreeMap::new();
+        let mut expiry: BTreeMap<(usize, String), Vec<i64>> = BTreeMap::new();
+        let order = |pool: &str| self.pools.iter().position(|(n, _)| n == pool).unwrap_or(usize::MAX);
+        for (name, _) in self.pools.iter() {
+            counts.insert((order(name), name.clone()), [0; 5]);
+        }
+
+        for lease in effective.all() {
+            let pool = self.pool_of(&lease.ip);
+            let key = (order(&pool), pool);
+            let state = lease_state(&lease, at);
+
+            let ndx = STATES.iter().position(|s| *s == state).unwrap_or(STATES.len() - 1);
+            counts.entry(key.clone()).or_insert([0; 5])[ndx] += 1;
+
+            if state == "active" {
+                if let Some(ends) = lease.dates.ends {
+                    let seconds = (ends.to_chrono() - at).num_seconds();
+                    expiry.entry(key).or_default().push(seconds);
+                }
+            }
+        }
+
+        let clients: HashSet<String> = leases
+            .all()
+            .into_iter()
+            .filter_map(|l| l.hardware.map(|h| h.mac.to_lowercase()))
+            .collect();
+
+        let mut out = String::new();
+
+        out.push_str("# TYPE dhcpd_leases gauge\n");
+        out.push_str("# HELP dhcpd_leases Number of effective leases by pool and state.\n");
+        for ((_, pool), states) in counts.iter() {
+            for (state, count) in STATES.iter().zip(states.iter()) {
+                writeln!(out, "dhcpd_leases{{pool=\"{}\",state=\"{}\"}} {}", escape(pool), state, count).unwrap();
+            }
+        }
+
+        out.push_str("# TYPE dhcpd_lease_expiry_seconds histogram\n");
+        out.push_str("# UNIT dhcpd_lease_expiry_seconds seconds\n");
+        out.push_str("# HELP dhcpd_lease_expiry_seconds Time until the active leases expire.\n");
+        for ((_, pool), seconds) in expiry.iter() {
+            let pool = escape(pool);
+            for le in self.buckets.iter() {
+                let count = seconds.iter().filter(|s| **s <= *le as i64).count();
+                writeln!(out, "dhcpd_lease_expiry_seconds_bucket{{pool=\"{}\",le=\"{}\"}} {}", pool, le, count).unwrap();
+            }
+            writeln!(out, "dhcpd_lease_expiry_seconds_bucket{{pool=\"{}\",le=\"+Inf\"}} {}", pool, seconds.len()).unwrap();
+            writeln!(out, "dhcpd_lease_expiry_seconds_sum{{pool=\"{}\"}} {}", pool, seconds.iter().sum::<i64>()).unwrap();
+            writeln!(out, "dhcpd_lease_expiry_seconds_count{{pool=\"{}\"}} {}", pool, seconds.len()).unwrap();
+        }
+
+        out.push_str("# TYPE dhcpd_lease_entries gauge\n");
+        out.push_str("# HELP dhcpd_lease_entries Number of lease entries in the file.\n");
+        writeln!(out, "dhcpd_lease_entries {}", leases.count()).unwrap();
+
+        out.push_str("# TYPE dhcpd_clients gauge\n");
+        out.push_str("# HELP dhcpd_clients Number of distinct client MAC addresses in the file.\n");
+        writeln!(out, "dhcpd_clients {}", clients.len()).unwrap();
+
+        out.push_str("# TYPE dhcpd_leases_parse_errors counter\n");
+        out.push_str("# HELP dhcpd_leases_parse_errors Number of times the leases file failed to parse.\n");
+        writeln!(out, "dhcpd_leases_parse_errors_total {}", self.parse_errors).unwrap();
+
+        if let Some(modified) = self.file_modified {
+            let age = DateTime::<Utc>::from(modified);
+            out.push_str("# TYPE dhcpd_leases_file_age_seconds gauge\n");
+            out.push_str("# UNIT dhcpd_leases_file_age_seconds seconds\n");
+            out.push_str("# HELP dhcpd_leases_file_age_seconds Time since the leases file was last modified.\n");
+            writeln!(out, "dhcpd_leases_file_age_seconds {}", (at - age).num_seconds()).unwrap();
+        }
+
+        out.push_str("# EOF\n");
+        out
+    }
+}
+
+/// Escape a label value (backslash, double quote and line feed).
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
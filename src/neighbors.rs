@@ -0,0 +1,244 @@
+//! Comparison of the leases with the neighbor (ARP) table of a host on the network.
+//!
+//! The neighbor table lists the IPs seen on the network with their MAC address. It is read from
+//! either of the text formats, loaded from a file or a string so it can be collected elsewhere:
+//!
+//! * The "/proc/net/arp" file of Linux
+//! * The output of the "ip neigh" command
+//!
+//! The [NeighborCheck] joins the neighbors with the active effective leases (the last entry for
+//! each IP, see [LeaseFilterBuilder::effective]) and reports:
+//!
+//! * The IPs seen without an active lease, i.e., a device with a static IP within a pool
+//! * The IPs seen with another MAC address than the one of the active lease
+//! * The active leases of the clients that are not in the table
+//!
+//! The table only holds the neighbors on the segments of the host, for a while after they were
+//! last seen, so the absent clients are not necessarily gone.
+//!
+//! ```rust
+//! use dhcpd_parser::neighbors::{self, NeighborCheck};
+//! use dhcpd_parser::leases::{Leases, LeasesMethods};
+//!
+//! let table = neighbors::parse("192.168.0.2 dev eth0 lladdr 00:11:22:33:44:55 REACHABLE
+//! 192.168.0.3 dev eth0  FAILED
+//! ").unwrap();
+//!
+//! let report = NeighborCheck::new().report(&table, &Leases::new());
+//!
+//! assert_eq!(table[0].mac.as_deref(), Some("00:11:22:33:44:55"));
+//! assert_eq!(report.unleased.len(), 1);
+//! ```
+use std::collections::HashMap;
+use std::fs;
+use std::net::{IpAddr, Ipv4Addr};
+use std::path::Path;
+
+use chrono::prelude::*;
+
+use crate::common::IpRange;
+use crate::leases::{Lease, Leases, LeasesMethods};
+use crate::metrics::lease_state;
+use crate::parser::decode_bytes;
+use crate::util::LeaseFilterBuilder;
+
+/// An entry of the neighbor table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Neighbor {
+    pub ip: IpAddr,
+    /// The MAC address, None if it was not resolved (i.e., "INCOMPLETE" or "FAILED")
+    pub mac: Option<String>,
+    /// The network interface (i.e., "eth0")
+    pub device: Option<String>,
+    /// The state of the entry for "ip neigh" (i.e., "REACHABLE" or "STALE"), or the flags of
+    /// "/proc/net/arp" (i.e., "0x2")
+    pub state: Option<String>,
+}
+
+/// Normalize a MAC address for comparison: lowercase, with 2 digits per byte.
+fn normalize_mac(mac: &str) -> String {
+    mac.split(':').map(|b| format!("{:0>2}", b.to_lowercase())).collect::<Vec<String>>().join(":")
+}
+
+fn parse_ip(ip: &str) -> Result<IpAddr, String> {
+    ip.parse::<IpAddr>().map_err(|_| format!("'{}' is not a valid IP address", ip))
+}
+
+/// Parse the contents of the "/proc/net/arp" file, with or without its header line.
+pub fn parse_arp(input: &str) -> Result<Vec<Neighbor>, String> {
+    let mut neighbors = Vec::new();
+
+    for (ndx, line) in input.lines().enumerate() {
+        if line.trim().is_empty() || line.starts_with("IP address") {
+            continue;
+        }
+
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        match fields.as_slice() {
+            [ip, _hw_type, flags, mac, _mask, device] => {
+                // The flags are 0x0 until the address is resolved
+                let resolved = *flags != "0x0" && *mac != "00:00:00:00:00:00";
+                neighbors.push(Neighbor {
+                    ip: parse_ip(ip).map_err(|e| format!("line {}: {}", ndx + 1, e))?,
+                    mac: if resolved { Some(normalize_mac(mac)) } else { None },
+                    device: Some((*device).to_owned()),
+                    state: Some((*flags).to_owned()),
+                });
+            }
+            _ => return Err(format!("line {}: Expected 6 columns, found '{}'", ndx + 1, line.trim())),
+        }
+    }
+
+    Ok(neighbors)
+}
+
+/// Parse the output of the "ip neigh" command (i.e., "192.168.0.2 dev eth0 lladdr
+/// 00:11:22:33:44:55 REACHABLE"). The "router" and "proxy" flags are skipped.
+pub fn parse_neigh(input: &str) -> Result<Vec<Neighbor>, String> {
+    let mut neighbors = Vec::new();
+
+    for (ndx, line) in input.lines().enumerate() {
+        let mut words = line.split_whitespace();
+        let ip = match words.next() {
+            Some(ip) => parse_ip(ip).map_err(|e| format!("line {}: {}", ndx + 1, e))?,
+            None => continue,
+        };
+
+        let mut neighbor = Neighbor { ip, mac: None, device: None, state: None };
+        while let Some(word) = words.next() {
+            let mut value = || {
+                words
+                    .next()
+                    .map(|v| v.to_owned())
+                    .ok_or_else(|| format!("line {}: Expected a value after '{}'", ndx + 1, word))
+            };
+
+            match word {
+                "dev" => neighbor.device = Some(value()?),
+                "lladdr" => neighbor.mac = Some(normalize_mac(&value()?)),
+                "router" | "proxy" | "extern_learn" => (),
+                state if state.chars().all(|c| c.is_ascii_uppercase() || c == '_') => {
+                    neighbor.state = Some(state.to_owned())
+                }
+                _ => log::debug!("Ignoring the neighbor attribute '{}'", word),
+            }
+        }
+
+        neighbors.push(neighbor);
+    }
+
+    Ok(neighbors)
+}
+
+/// Parse a neighbor table of either format, recognized by the header line of "/proc/net/arp".
+pub fn parse(input: &str) -> Result<Vec<Neighbor>, String> {
+    if input.trim_start().starts_with("IP address") {
+        parse_arp(input)
+    } else {
+        parse_neigh(input)
+    }
+}
+
+/// Load and parse a neighbor table of either format, i.e., "/proc/net/arp".
+pub fn parse_file<P: AsRef<Path>>(path: P) -> Result<Vec<Neighbor>, String> {
+    let path = path.as_ref();
+    let bytes = fs::read(path).map_err(|e| format!("Failed to open: {}. Error: {}", path.display(), e))?;
+    let contents = decode_bytes(&bytes);
+
+    parse(&contents).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+/// A neighbor with another MAC address than the active lease of its IP.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mismatch {
+    pub neighbor: Neighbor,
+    pub lease: Lease,
+}
+
+/// The result of [NeighborCheck::report].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NeighborReport {
+    /// The neighbors without an active lease for their IP
+    pub unleased: Vec<Neighbor>,
+    /// The neighbors with another MAC address than the active lease
+    pub mismatches: Vec<Mismatch>,
+    /// The active leases of the IPs that are not in the table (or not resolved)
+    pub absent: Vec<Lease>,
+}
+
+/// Builder for the [NeighborReport] of a neighbor table against the leases.
+pub struct NeighborCheck {
+    pools: Vec<IpRange>,
+    at: Option<DateTime<Utc>>,
+}
+
+impl Default for NeighborCheck {
+    fn default() -> Self {
+        NeighborCheck::new()
+    }
+}
+
+impl NeighborCheck {
+    pub fn new() -> NeighborCheck {
+        NeighborCheck { pools: Vec::new(), at: None }
+    }
+
+    /// Only check the IPs within the range. When no range is given, all of the IPv4 addresses
+    /// are checked.
+    pub fn pool(&mut self, range: IpRange) -> &mut Self {
+        self.pools.push(range);
+        self
+    }
+
+    /// Evaluate the leases at the instant `dt` rather than [Utc::now()].
+    pub fn at(&mut self, dt: DateTime<Utc>) -> &mut Self {
+        self.at = Some(dt);
+        self
+    }
+
+    fn checked(&self, ip: Ipv4Addr) -> bool {
+        self.pools.is_empty() || self.pools.iter().any(|r| r.contains(ip))
+    }
+
+    /// Compare the resolved IPv4 neighbors with the active effective leases.
+    pub fn report(&self, neighbors: &[Neighbor], leases: &Leases) -> NeighborReport {
+        let at = self.at.unwrap_or_else(Utc::now);
+        let effective = LeaseFilterBuilder::new(leases).effective().collect().all();
+
+        let active: HashMap<Ipv4Addr, &Lease> = effective
+            .iter()
+            .filter(|l| lease_state(l, at) == "active")
+            .filter_map(|l| l.ip.parse::<Ipv4Addr>().ok().map(|ip| (ip, l)))
+            .filter(|(ip, _)| self.checked(*ip))
+            .collect();
+
+        let mut report = NeighborReport { unleased: Vec::new(), mismatches: Vec::new(), absent: Vec::new() };
+        let mut seen: HashMap<Ipv4Addr, &Neighbor> = HashMap::new();
+
+        for neighbor in neighbors {
+            let (ip, mac) = match (neighbor.ip, &neighbor.mac) {
+                (IpAddr::V4(ip), Some(mac)) if self.checked(ip) => (ip, mac),
+                _ => continue,
+            };
+            seen.insert(ip, neighbor);
+
+            match active.get(&ip) {
+                None => report.unleased.push(neighbor.clone()),
+                Some(lease) => {
+                    let leased = lease.hardware.as_ref().map(|hw| normalize_mac(&hw.mac));
+                    if leased.as_ref() != Some(mac) {
+                        report.mismatches.push(Mismatch { neighbor: neighbor.clone(), lease: (*lease).clone() });
+                    }
+                }
+            }
+        }
+
+        report.absent = effective
+            .iter()
+            .filter(|l| l.ip.parse::<Ipv4Addr>().is_ok_and(|ip| active.contains_key(&ip) && !seen.contains_key(&ip)))
+            .cloned()
+            .collect();
+
+        report
+    }
+}
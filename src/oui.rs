@@ -0,0 +1,165 @@
+//! Vendor lookup of the MAC addresses in the IEEE registry (requires the "oui" feature).
+//!
+//! The [OuiDb] is loaded from the CSV files of the IEEE registry, downloaded beforehand (there is
+//! no network access), i.e.:
+//!
+//! * MA-L (the OUI, 24 bits): <https://standards-oui.ieee.org/oui/oui.csv>
+//! * MA-M (28 bits): <https://standards-oui.ieee.org/oui28/mam.csv>
+//! * MA-S (36 bits): <https://standards-oui.ieee.org/oui36/oui36.csv>
+//!
+//! The files all have the columns "Registry,Assignment,Organization Name,Organization Address",
+//! with the assignment as the hexadecimal prefix (i.e., "B827EB"). The prefixes are held in a
+//! prefix tree of the hexadecimal digits, and a MAC address gets the vendor of its longest
+//! matching prefix: the MA-M and MA-S blocks are within MA-L blocks of the IEEE Registration
+//! Authority.
+//!
+//! ```rust
+//! use dhcpd_parser::oui::OuiDb;
+//!
+//! let db = OuiDb::from("Registry,Assignment,Organization Name,Organization Address
+//! MA-L,B827EB,Raspberry Pi Foundation,Mitchell Wood House Caldecote Cambridgeshire GB CB23 7NU
+//! ").unwrap();
+//!
+//! assert_eq!(db.vendor("b8:27:eb:12:34:56"), Some("Raspberry Pi Foundation"));
+//! ```
+use std::fs;
+use std::path::Path;
+
+/// A node of the prefix tree, for a hexadecimal digit.
+#[derive(Debug, Clone, Default)]
+struct Node {
+    children: [Option<Box<Node>>; 16],
+    vendor: Option<String>,
+}
+
+/// The registry of the vendors of the MAC address blocks.
+#[derive(Debug, Clone, Default)]
+pub struct OuiDb {
+    root: Node,
+    len: usize,
+}
+
+/// Split a CSV line into its fields, removing the quotes of the quoted fields (RFC 4180).
+fn split_csv(line: &str) -> Vec<String> {
+    let mut fields = vec![String::new()];
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match (c, quoted) {
+            ('"', true) if chars.peek() == Some(&'"') => {
+                chars.next();
+                fields.last_mut().unwrap().push('"');
+            }
+            ('"', _) => quoted = !quoted,
+            (',', false) => fields.push(String::new()),
+            (c, _) => fields.last_mut().unwrap().push(c),
+        }
+    }
+
+    fields
+}
+
+/// The hexadecimal digits of a MAC address or of a prefix. The octets separated by ':', '-' or
+/// '.' can be written with a single digit (i.e., "0:1e:c2").
+fn nibbles(value: &str) -> Option<Vec<usize>> {
+    let parts: Vec<&str> = value.split([':', '-', '.']).collect();
+    let pad = parts.len() > 1;
+
+    parts
+        .iter()
+        .flat_map(|part| {
+            let padding = if pad && part.len() == 1 { Some('0') } else { None };
+            padding.into_iter().chain(part.chars())
+        })
+        .map(|c| c.to_digit(16).map(|d| d as usize))
+        .collect()
+}
+
+impl OuiDb {
+    pub fn new() -> OuiDb {
+        OuiDb::default()
+    }
+
+    /// Parse the contents of a CSV file of the IEEE registry.
+    pub fn from(input: &str) -> Result<OuiDb, String> {
+        let mut db = OuiDb::new();
+        db.load(input)?;
+        Ok(db)
+    }
+
+    /// Load the CSV files of the IEEE registry, i.e., the MA-L, MA-M and MA-S files. The bytes
+    /// that are not valid UTF-8 are replaced with U+FFFD in the organization names.
+    pub fn from_files<P: AsRef<Path>>(paths: &[P]) -> Result<OuiDb, String> {
+        let mut db = OuiDb::new();
+
+        for path in paths {
+            let path = path.as_ref();
+            let bytes = fs::read(path).map_err(|e| format!("Failed to open: {}. Error: {}", path.display(), e))?;
+            db.load(&String::from_utf8_lossy(&bytes)).map_err(|e| format!("{}: {}", path.display(), e))?;
+        }
+
+        Ok(db)
+    }
+
+    /// Add the assignments of the contents of a CSV file of the IEEE registry.
+    pub fn load(&mut self, input: &str) -> Result<(), String> {
+        for (ndx, line) in input.lines().enumerate() {
+            if line.trim().is_empty() || line.starts_with("Registry,") {
+                continue;
+            }
+
+            let fields = split_csv(line);
+            match fields.as_slice() {
+                [_, assignment, vendor, ..] => {
+                    self.insert(assignment, vendor.trim()).map_err(|e| format!("line {}: {}", ndx + 1, e))?
+                }
+                _ => return Err(format!("line {}: Expected the assignment and the organization name", ndx + 1)),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Add the vendor of a prefix in hexadecimal, i.e., "B827EB" or "b8:27:eb".
+    pub fn insert(&mut self, prefix: &str, vendor: &str) -> Result<(), String> {
+        let digits = nibbles(prefix)
+            .filter(|d| !d.is_empty())
+            .ok_or_else(|| format!("'{}' is not a hexadecimal prefix", prefix))?;
+
+        let mut node = &mut self.root;
+        for digit in digits {
+            node = node.children[digit].get_or_insert_with(Box::default);
+        }
+
+        if node.vendor.replace(vendor.to_owned()).is_none() {
+            self.len += 1;
+        }
+        Ok(())
+    }
+
+    /// The vendor of the longest prefix matching the MAC address.
+    pub fn vendor(&self, mac: &str) -> Option<&str> {
+        let mut node = &self.root;
+        let mut vendor = None;
+
+        for digit in nibbles(mac)? {
+            node = match &node.children[digit] {
+                Some(child) => child,
+                None => break,
+            };
+            vendor = node.vendor.as_deref().or(vendor);
+        }
+
+        vendor
+    }
+
+    /// The number of prefixes.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
@@ -1,15 +1,46 @@
-use crate::leases::parse_lease;
+use std::collections::VecDeque;
+use std::fmt;
+use std::fs;
+use std::io::BufRead;
+#[cfg(any(feature = "gzip", feature = "zstd", feature = "xz"))]
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::iter::Peekable;
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+use chrono::NaiveDateTime;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::client;
+use crate::config::{self, Block, Statement};
+use crate::leases::{next_text, parse_lease, parse_lease_ref};
 use crate::leases::Lease;
+use crate::leases::LeaseRef;
 use crate::leases::Leases;
 #[doc(inline)]
 pub use crate::leases::LeasesMethods;
 use crate::lex::lex;
-use crate::lex::LexItem;
+use crate::lex::{LexItem, Lexer, Token, TokenKind};
 
 /// Result for success returning a [Leases] instance.
+///
+/// The struct is `non_exhaustive` so that fields can be added without breaking the users; create
+/// it with [ParserResult::new].
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[non_exhaustive]
 pub struct ParserResult {
     pub leases: Leases,
+    /// The file the leases were loaded from, when using [parse_file].
+    pub source: Option<PathBuf>,
+}
+
+impl ParserResult {
+    /// Create with the leases, without a source file.
+    pub fn new(leases: Leases) -> ParserResult {
+        ParserResult { leases, source: None }
+    }
 }
 
 /// Keyword "lease"
@@ -26,97 +57,130 @@ impl ConfigKeyword {
     }
 
     pub fn from(s: &str) -> Result<ConfigKeyword, String> {
+        ConfigKeyword::lookup(s).ok_or_else(|| format!("'{}' declaration is not supported", s))
+    }
+
+    /// Same as [ConfigKeyword::from], without allocating an error for the words that are not keywords.
+    pub(crate) fn lookup(s: &str) -> Option<ConfigKeyword> {
         match s {
-            "lease" => Ok(ConfigKeyword::Lease),
-            _ => Err(format!("'{}' declaration is not supported", s)),
+            "lease" => Some(ConfigKeyword::Lease),
+            _ => None,
         }
     }
 }
 
-/// Parse the config represented by a vector of tokens.
-fn parse_config(tokens: Vec<LexItem>) -> Result<ParserResult, String> {
+/// Parse the next top-level statement from the token iterator.
+///
+/// Returns the [Lease] if the statement was a "lease" declaration, or `None` for the other
+/// top-level statements (i.e., "authoring-byte-order" and the ignored keywords). The value of
+/// "authoring-byte-order" is retained in `bite_order` and applied to each following lease.
+fn parse_statement<'l, T: Iterator<Item = &'l LexItem>>(
+    it: &mut Peekable<T>,
+    bite_order: &mut Option<String>,
+) -> Result<Option<Lease>, String> {
     use crate::leases::LeaseKeyword;
 
-    let mut leases = Leases::new();
-    let lease = Lease::new();
-    let mut bite_order : Option<String> = None;
-
-    let mut it = tokens.iter().peekable();
-
-    while let Some(token) = it.peek() {
-        match token {
-            LexItem::Decl(ConfigKeyword::Lease) => {
-                if lease != Lease::new() {
-                    leases.push(lease.clone());
-                }
+    match it.peek() {
+        Some(LexItem::Decl(ConfigKeyword::Lease)) => {
+            let mut lease = Lease::new();
+            // ip-address
+            it.next();
+            lease.ip = match it.peek() {
+                Some(ip) => ip.to_string(),
+                None => return Err("IP address expected. Found end of input".to_owned()),
+            };
 
-                let mut lease = Lease::new();
-                // ip-address
-                it.next();
-                lease.ip = it.peek().expect("IP address expected").to_string();
+            // left curly brace
+            it.next();
+            match it.peek() {
+                Some(LexItem::Paren('{')) => (),
+                other => return Err(format!("Expected start of section with '{{', got '{:?}'", other)),
+            }
 
-                // left curly brace
-                it.next();
-                assert_eq!(it.peek().unwrap().to_owned(), &LexItem::Paren('{'));
+            // statements for the lease
+            it.next();
+            parse_lease(&mut lease, it)?;
 
-                // statements for the lease
-                it.next();
-                parse_lease(&mut lease, &mut it)?;
-
-                // right curly brace
-                if it.peek().is_none() || it.peek().unwrap().to_owned() != &LexItem::Paren('}') {
-                    return Err(format!(
-                        "Expected end of section with '}}', got '{:?}'",
-                        it.peek(),
-                    ));
-                }
+            // right curly brace
+            if it.peek().is_none() || it.peek().unwrap().to_owned() != &LexItem::Paren('}') {
+                return Err(format!(
+                    "Expected end of section with '}}', got '{:?}'",
+                    it.peek(),
+                ));
+            }
 
-                // Set the bite_order if supplied (Linux only)
-                lease.byte_order = bite_order.clone();
+            // Set the bite_order if supplied (Linux only)
+            lease.byte_order = bite_order.clone();
 
-                leases.push(lease.clone());
-                it.next();
+            it.next();
+            Ok(Some(lease))
+        }
+        Some(LexItem::Opt(LeaseKeyword::BiteOrder)) => {
+            it.next();
+            match it.next_if(|&k | k != &LexItem::Endl) {
+                Some(val) => {
+                    // println!("Found: author-bite-order: {}", val.to_string());
+                    bite_order.replace(val.to_string());
+                },
+                None => return Err(format!("Expected author-bite-order value. Found endl instead"))
+            };
+            match it.next_if_eq(&&LexItem::Endl) {
+                Some(_) => (),
+                None => return Err(format!("Expected semicolon after author-bite-order term")),
             }
-            LexItem::Opt(LeaseKeyword::BiteOrder) => {
-                it.next();
-                match it.next_if(|&k | k != &LexItem::Endl) {
-                    Some(val) => {
-                        // println!("Found: author-bite-order: {}", val.to_string());
-                        bite_order = Some(val.to_string());
-                    },
-                    None => return Err(format!("Expected author-bite-order value. Found endl instead"))
+            Ok(None)
+        }
+        Some(LexItem::Opt(LeaseKeyword::Ignored)) => {
+            it.next();
+            // Consume up to the endl
+            loop {
+                match it.next_if(|&k | k != &LexItem::Endl && k != &LexItem::Paren('}')) {
+                    Some(_) => (),  // println!("Skipping: {}", token),
+                    None => break
                 };
-                match it.next_if_eq(&&LexItem::Endl) {
-                    Some(_) => (),
-                    None => return Err(format!("Expected semicolon after author-bite-order term")),
-                }
-            }
-            LexItem::Opt(LeaseKeyword::Ignored) => {
-                it.next();
-                // Consume up to the endl
-                loop {
-                    match it.next_if(|&k | k != &LexItem::Endl && k != &LexItem::Paren('}')) {
-                        Some(_) => (),  // println!("Skipping: {}", token),
-                        None => break
-                    };
-                }
-                it.next();
-            }
-            _ => {
-                return Err(format!("Unexpected {:?}", it.peek()));
             }
+            it.next();
+            Ok(None)
+        }
+        _ => {
+            Err(format!("Unexpected {:?}", it.peek()))
         }
     }
+}
+
+/// Parse the leases represented by a slice of tokens, applying the "authoring-byte-order" value
+/// from `bite_order` until it is replaced by a statement in the tokens.
+fn parse_tokens(tokens: &[LexItem], bite_order: &mut Option<String>) -> Result<Vec<Lease>, String> {
+    let mut leases = Vec::new();
+    let mut it = tokens.iter().peekable();
 
-    Ok(ParserResult { leases })
+    while it.peek().is_some() {
+        if let Some(lease) = parse_statement(&mut it, bite_order)? {
+            leases.push(lease);
+        }
+    }
+
+    Ok(leases)
+}
+
+/// Parse the config represented by a vector of tokens.
+fn parse_config(tokens: Vec<LexItem>) -> Result<ParserResult, String> {
+    let mut leases = Leases::new();
+    let mut bite_order : Option<String> = None;
+
+    for lease in parse_tokens(&tokens, &mut bite_order)? {
+        leases.push(lease);
+    }
+
+    Ok(ParserResult::new(leases))
 }
 
 /// Parse the String containing the contents of the leases file.
 ///
 /// # Overview
 ///
-/// The "dhcpd.leases" contents should be loaded into a [String] to pass into this function. Note
-/// that this library does not support loading the leases file. On Linux the default file is generally
+/// The "dhcpd.leases" contents should be loaded into a [String] to pass into this function. To load
+/// the leases file from a path, use [parse_file]. On Linux the default file is generally
 /// found in "/var/lib/dhcp/dhcpd.leases".
 ///
 /// This will parse the contents of the dhcpd.leases file and, if successful, return a [ParserResult]
@@ -184,3 +248,826 @@ where
     let tokens = lex(input).unwrap();
     return parse_config(tokens);
 }
+
+/// Decode the contents of the leases file as UTF-8, replacing each byte of an invalid sequence with
+/// its octal escape (i.e., "\\351") as ISC dhcpd does when writing the non-printable characters.
+pub(crate) fn decode_bytes(bytes: &[u8]) -> String {
+    let mut result = String::with_capacity(bytes.len());
+    let mut rest = bytes;
+
+    loop {
+        match std::str::from_utf8(rest) {
+            Ok(valid) => {
+                result.push_str(valid);
+                return result;
+            }
+            Err(e) => {
+                let (valid, invalid) = rest.split_at(e.valid_up_to());
+                // The prefix was validated by from_utf8
+                result.push_str(std::str::from_utf8(valid).unwrap());
+
+                let len = e.error_len().unwrap_or(invalid.len());
+                for b in &invalid[..len] {
+                    result.push_str(&format!("\\{:03o}", b));
+                }
+                rest = &invalid[len..];
+            }
+        }
+    }
+}
+
+/// Decompress the contents of an archived leases file, detected by the magic bytes of the
+/// gzip, zstd and xz formats. Returns the contents as-is if not compressed.
+fn decompress(path: &Path, bytes: Vec<u8>) -> Result<Vec<u8>, String> {
+    let format = if bytes.starts_with(&[0x1f, 0x8b]) {
+        "gzip"
+    } else if bytes.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+        "zstd"
+    } else if bytes.starts_with(&[0xfd, b'7', b'z', b'X', b'Z', 0x00]) {
+        "xz"
+    } else {
+        return Ok(bytes);
+    };
+
+    match format {
+        #[cfg(feature = "gzip")]
+        "gzip" => read_all(path, flate2::read::MultiGzDecoder::new(bytes.as_slice())),
+        #[cfg(feature = "zstd")]
+        "zstd" => match zstd::stream::read::Decoder::new(bytes.as_slice()) {
+            Ok(decoder) => read_all(path, decoder),
+            Err(e) => Err(format!("Failed to decompress: {}. Error: {}", path.display(), e)),
+        },
+        #[cfg(feature = "xz")]
+        "xz" => read_all(path, xz2::read::XzDecoder::new(bytes.as_slice())),
+        _ => Err(format!(
+            "{} is {} compressed. Enable the \"{}\" feature to load it",
+            path.display(),
+            format,
+            format,
+        )),
+    }
+}
+
+#[cfg(any(feature = "gzip", feature = "zstd", feature = "xz"))]
+fn read_all<R: Read>(path: &Path, mut reader: R) -> Result<Vec<u8>, String> {
+    let mut result = Vec::new();
+    reader
+        .read_to_end(&mut result)
+        .map_err(|e| format!("Failed to decompress: {}. Error: {}", path.display(), e))?;
+
+    Ok(result)
+}
+
+/// Load and parse the leases file at `path`.
+///
+/// # Overview
+///
+/// This reads the file and passes its contents to [parse]. The returned [ParserResult] records the
+/// path of the file that was actually loaded in [source](ParserResult::source).
+///
+/// * If the file does not exist, the "dhcpd.leases~" backup is loaded instead. dhcpd renames the
+///   file to the backup while rewriting it, so the file can briefly be missing.
+/// * Archived leases compressed with gzip, zstd or xz are decompressed. This requires the "gzip",
+///   "zstd" or "xz" feature respectively; the format is detected from the contents, not the name.
+/// * Bytes that are not valid UTF-8 (e.g., in hostnames) are replaced with their octal escape,
+///   the same way ISC dhcpd writes the non-printable characters.
+///
+/// ```rust,no_run
+/// use dhcpd_parser::parser;
+/// use dhcpd_parser::leases::LeasesMethods;
+///
+/// let res = parser::parse_file("/var/lib/dhcp/dhcpd.leases").unwrap();
+///
+/// println!("Loaded {} leases from {:?}", res.leases.count(), res.source);
+/// ```
+pub fn parse_file<P: AsRef<Path>>(path: P) -> Result<ParserResult, String> {
+    let path = path.as_ref();
+
+    let source = if path.exists() {
+        path.to_path_buf()
+    } else {
+        let mut backup = path.as_os_str().to_owned();
+        backup.push("~");
+        let backup = PathBuf::from(backup);
+
+        if !backup.exists() {
+            return Err(format!("Failed to open: {}. File (and backup) not found", path.display()));
+        }
+        backup
+    };
+
+    let bytes = fs::read(&source)
+        .map_err(|e| format!("Failed to open: {}. Error: {}", source.display(), e))?;
+    let bytes = decompress(&source, bytes)?;
+
+    let mut res = parse(decode_bytes(&bytes))?;
+    res.source = Some(source);
+
+    Ok(res)
+}
+
+/// Load and parse the leases file at `path` without blocking the async runtime (requires the
+/// "tokio" feature).
+///
+/// This is the async counterpart of [parse_file], with the same handling of the backup file,
+/// compressed archives and non-UTF-8 bytes. Both the read and the parse run on the blocking
+/// thread pool of the [tokio] runtime.
+#[cfg(feature = "tokio")]
+pub async fn parse_file_async<P: AsRef<Path>>(path: P) -> Result<ParserResult, String> {
+    let path = path.as_ref().to_path_buf();
+
+    tokio::task::spawn_blocking(move || parse_file(path))
+        .await
+        .map_err(|e| format!("Failed to parse leases file. Error: {}", e))?
+}
+
+/// The dialect of a leases file, as written by the different DHCP servers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Dialect {
+    /// The OpenBSD dhcpd: dates in "UTC", no "binding state" and no failover statements
+    OpenBsd,
+    /// The ISC dhcpd for DHCPv4: "binding state", failover dates and "authoring-byte-order"
+    Isc,
+    /// The ISC dhcpd for DHCPv6: "ia-na", "ia-ta" and "ia-pd" declarations instead of "lease"
+    IscV6,
+}
+
+impl fmt::Display for Dialect {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Dialect::OpenBsd => write!(f, "OpenBSD"),
+            Dialect::Isc => write!(f, "ISC"),
+            Dialect::IscV6 => write!(f, "ISC DHCPv6"),
+        }
+    }
+}
+
+impl Dialect {
+    /// The statements of the dialect within the `scope`: the keyword of the enclosing declaration,
+    /// or "" for the top level. `None` if the statements of the scope are not checked.
+    fn statements(&self, scope: &str) -> Option<&'static [&'static str]> {
+        match (self, scope) {
+            (Dialect::OpenBsd, "") => Some(&["lease"]),
+            (Dialect::OpenBsd, "lease") => {
+                Some(&["starts", "ends", "hardware", "uid", "client-hostname", "hostname", "abandoned"])
+            }
+            (Dialect::Isc, "") => Some(&["lease", "authoring-byte-order", "server-duid", "failover"]),
+            (Dialect::Isc, "lease") => Some(&[
+                "starts", "ends", "tstp", "tsfp", "atsfp", "cltt", "binding", "next", "rewind", "hardware",
+                "uid", "client-hostname", "option", "set", "on", "bootp", "reserved",
+            ]),
+            (Dialect::IscV6, "") => Some(&["authoring-byte-order", "server-duid", "ia-na", "ia-ta", "ia-pd", "failover"]),
+            (Dialect::IscV6, "ia-na") | (Dialect::IscV6, "ia-ta") | (Dialect::IscV6, "ia-pd") => {
+                Some(&["cltt", "iaaddr", "iaprefix"])
+            }
+            (Dialect::IscV6, "iaaddr") | (Dialect::IscV6, "iaprefix") => Some(&[
+                "binding", "next", "rewind", "preferred-life", "max-life", "ends", "set", "on", "option",
+            ]),
+            _ => None,
+        }
+    }
+}
+
+/// Guess the [Dialect] of the contents of a leases file.
+///
+/// The contents are sniffed for the statements particular to each dialect:
+///
+/// * IscV6: the "ia-na", "ia-ta" and "ia-pd" declarations, or "server-duid"
+/// * Isc: the header comment of isc-dhcp, "authoring-byte-order", the binding states, the failover
+///   dates ("tstp", "tsfp", "atsfp", "cltt"), "hardware ethernet" and the dates without "UTC"
+/// * OpenBsd: the dates with the "UTC" suffix and "hardware type"
+///
+/// The dialect with the most hints wins; without any hint (i.e., an empty file) it is [Dialect::Isc].
+///
+/// ```rust
+/// use dhcpd_parser::parser::{self, Dialect};
+///
+/// let dialect = parser::detect_format("lease 192.168.0.2 {
+///     starts 2 2019/01/01 22:00:00 UTC;
+///     ends 2 2019/01/01 23:00:00 UTC;
+/// }");
+///
+/// assert_eq!(dialect, Dialect::OpenBsd);
+/// ```
+pub fn detect_format(input: &str) -> Dialect {
+    let mut bsd = 0;
+    let mut isc = 0;
+
+    for line in input.lines() {
+        let line = line.trim();
+
+        if let Some(comment) = line.strip_prefix('#') {
+            if comment.contains("isc-dhcp") || comment.contains("dhcpd.leases(5)") {
+                isc += 1;
+            }
+            continue;
+        }
+
+        let words: Vec<&str> = line.trim_end_matches(';').split_whitespace().collect();
+        match words.as_slice() {
+            ["ia-na" | "ia-ta" | "ia-pd" | "server-duid", ..] => return Dialect::IscV6,
+            ["authoring-byte-order", ..] | ["binding" | "next" | "rewind", ..] => isc += 1,
+            ["tstp" | "tsfp" | "atsfp" | "cltt", ..] => isc += 1,
+            ["hardware", "type", ..] => bsd += 1,
+            ["hardware", "ethernet", ..] => isc += 1,
+            ["starts" | "ends", _, _, _, "UTC"] => bsd += 1,
+            ["starts" | "ends", _, _, _] => isc += 1,
+            _ => (),
+        }
+    }
+
+    if bsd > isc {
+        Dialect::OpenBsd
+    } else {
+        Dialect::Isc
+    }
+}
+
+/// The values of the binding states of the ISC dialects.
+const BINDING_STATES: &[&str] = &["free", "active", "expired", "released", "abandoned", "reset", "backup", "bootp", "reserved"];
+
+/// The statements that open a block rather than end with ';'.
+const BLOCKS: &[&str] = &["lease", "ia-na", "ia-ta", "ia-pd", "iaaddr", "iaprefix", "on", "failover"];
+
+/// Check a date of the form "weekday yyyy/mm/dd hh:mm:ss".
+fn is_date(weekday: &str, date: &str, time: &str) -> bool {
+    weekday.parse::<u8>().is_ok_and(|w| w <= 6)
+        && NaiveDateTime::parse_from_str(&format!("{} {}", date, time), "%Y/%m/%d %H:%M:%S").is_ok()
+}
+
+/// Check a MAC address of the "hardware" statement, i.e., "00:11:22:33:44:55".
+fn is_mac(mac: &str) -> bool {
+    mac.split(':').all(|b| !b.is_empty() && b.len() <= 2 && b.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+impl Dialect {
+    /// Check the arguments of a statement against the grammar of the dialect's man page.
+    fn check_arguments(&self, keyword: &str, args: &[&str]) -> Result<(), String> {
+        let found = || if args.is_empty() { "nothing".to_owned() } else { format!("'{}'", args.join(" ")) };
+        let expects = |grammar: &str| Err(format!("'{}' expects '{}', found {}", keyword, grammar, found()));
+
+        match (self, keyword, args) {
+            (_, "lease", [ip]) if ip.parse::<Ipv4Addr>().is_ok() => Ok(()),
+            (_, "lease", _) => expects("ipv4-address"),
+            (_, "ia-na" | "ia-ta" | "ia-pd", [_]) => Ok(()),
+            (_, "ia-na" | "ia-ta" | "ia-pd", _) => expects("iaid-duid"),
+            (_, "iaaddr", [ip]) if ip.parse::<Ipv6Addr>().is_ok() => Ok(()),
+            (_, "iaaddr", _) => expects("ipv6-address"),
+            (_, "iaprefix", [prefix]) if prefix.split_once('/').is_some_and(|(ip, len)| {
+                ip.parse::<Ipv6Addr>().is_ok() && len.parse::<u8>().is_ok_and(|l| l <= 128)
+            }) => Ok(()),
+            (_, "iaprefix", _) => expects("ipv6-address/length"),
+            (Dialect::OpenBsd, "starts" | "ends", [weekday, date, time, "UTC"]) if is_date(weekday, date, time) => Ok(()),
+            (Dialect::OpenBsd, "starts" | "ends", _) => expects("weekday yyyy/mm/dd hh:mm:ss UTC"),
+            (_, "starts" | "ends" | "tstp" | "tsfp" | "atsfp" | "cltt", [weekday, date, time]) if is_date(weekday, date, time) => Ok(()),
+            (_, "starts" | "ends" | "tstp" | "tsfp" | "atsfp" | "cltt", ["epoch", seconds]) if seconds.parse::<i64>().is_ok() => Ok(()),
+            (_, "ends" | "tstp" | "tsfp" | "atsfp", ["never"]) => Ok(()),
+            (_, "starts" | "ends" | "tstp" | "tsfp" | "atsfp" | "cltt", _) => expects("weekday yyyy/mm/dd hh:mm:ss"),
+            (_, "hardware", [_, mac]) if is_mac(mac) => Ok(()),
+            (_, "hardware", _) => expects("hardware-type mac-address"),
+            (_, "uid" | "client-hostname" | "hostname" | "server-duid", [_]) => Ok(()),
+            (_, "uid", _) => expects("client-identifier"),
+            (_, "client-hostname" | "hostname", _) => expects("\"hostname\""),
+            (_, "server-duid", _) => expects("duid"),
+            (_, "abandoned", []) => Ok(()),
+            (_, "abandoned", _) => expects(""),
+            (_, "binding", ["state", state]) | (_, "next" | "rewind", ["binding", "state", state])
+                if BINDING_STATES.contains(state) => Ok(()),
+            (_, "binding", _) => expects(&format!("state {}", BINDING_STATES.join("|"))),
+            (_, "next" | "rewind", _) => expects(&format!("binding state {}", BINDING_STATES.join("|"))),
+            (_, "authoring-byte-order", ["little-endian" | "big-endian"]) => Ok(()),
+            (_, "authoring-byte-order", _) => expects("little-endian|big-endian"),
+            (_, "preferred-life" | "max-life", [seconds]) if seconds.parse::<u32>().is_ok() => Ok(()),
+            (_, "preferred-life" | "max-life", _) => expects("seconds"),
+            _ => Ok(()),
+        }
+    }
+
+    /// Check a statement of the `scope` (the keyword of the enclosing declaration, or "" for the
+    /// top level). `block` is set if the statement opens a block.
+    fn check_statement(&self, scope: &str, words: &[&str], block: bool) -> Result<(), String> {
+        let (keyword, args) = match words.split_first() {
+            Some((keyword, args)) => (*keyword, args),
+            None => return Err("Empty statement".to_owned()),
+        };
+
+        if let Some(statements) = self.statements(scope) {
+            if !statements.contains(&keyword) {
+                let within = if scope.is_empty() { "at the top level".to_owned() } else { format!("in '{}'", scope) };
+                return Err(format!("'{}' is not valid {} of the {} dialect", keyword, within, self));
+            }
+        }
+
+        match (block, BLOCKS.contains(&keyword)) {
+            (true, false) => Err(format!("'{}' cannot open a block", keyword)),
+            (false, true) => Err(format!("'{}' expects a block", keyword)),
+            _ => self.check_arguments(keyword, args),
+        }
+    }
+}
+
+/// Check that each statement of the input is valid for the `dialect`: its keyword is one of the
+/// dialect within the enclosing declaration, and its arguments follow the grammar of the
+/// dialect's man page. The blocks of the other declarations (i.e., "on commit") are skipped.
+fn validate(input: &str, dialect: Dialect) -> Result<(), String> {
+    // The keywords of the enclosing declarations, and the words of the current statement
+    let mut scopes: Vec<&str> = Vec::new();
+    let mut words: Vec<&str> = Vec::new();
+    let mut start = 0;
+
+    let line = |offset: usize| input[..offset].matches('\n').count() + 1;
+    let checked = |scopes: &[&str]| scopes.iter().all(|s| dialect.statements(s).is_some());
+
+    for token in Lexer::new(input) {
+        match token.kind {
+            TokenKind::Endl | TokenKind::Paren('{') => {
+                let block = token.kind != TokenKind::Endl;
+                if checked(&scopes) {
+                    let scope = scopes.last().copied().unwrap_or_default();
+                    dialect
+                        .check_statement(scope, &words, block)
+                        .map_err(|e| format!("line {}: {}", line(start), e))?;
+                }
+                if block {
+                    scopes.push(words.first().copied().unwrap_or_default());
+                }
+                words.clear();
+            }
+            TokenKind::Paren('}') => {
+                if !words.is_empty() && checked(&scopes) {
+                    return Err(format!("line {}: Expected semicolon after '{}'", line(start), words.join(" ")));
+                }
+                if scopes.pop().is_none() {
+                    return Err(format!("line {}: Unexpected '}}'", line(token.span.start)));
+                }
+                words.clear();
+            }
+            _ => {
+                if words.is_empty() {
+                    start = token.span.start;
+                }
+                words.push(token.text);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Convert an "iaaddr" or "iaprefix" block of an IA declaration into a [Lease].
+fn parse_iaaddr(ia: &Block, block: &Block) -> Result<Lease, String> {
+    let mut lease = Lease::new();
+    lease.ip = block.words.get(1).cloned().ok_or("Address expected for 'iaaddr'")?;
+    lease.uid = ia.words.get(1).cloned();
+
+    for statement in ia.statements.iter().chain(block.statements.iter()) {
+        if let Statement::Parameter(p) = statement {
+            let state = || match p.values.as_slice() {
+                [state, value] if state == "state" => Ok(Some(value.clone())),
+                [binding, state, value] if binding == "binding" && state == "state" => Ok(Some(value.clone())),
+                _ => Err(format!("Invalid '{} {}' statement", p.name, p.values.join(" "))),
+            };
+
+            match p.name.as_str() {
+                "cltt" => lease.dates.cltt = client::parse_date(&p.name, &p.values)?,
+                "ends" => lease.dates.ends = client::parse_date(&p.name, &p.values)?,
+                "binding" => lease.binding_state = state()?,
+                "next" => lease.next_binding_state = state()?,
+                "rewind" => lease.rewind_binding_state = state()?,
+                _ => (),
+            }
+        }
+    }
+
+    Ok(lease)
+}
+
+/// Parse the contents of a DHCPv6 leases file of ISC dhcpd. Each "iaaddr" (or "iaprefix") of the
+/// IA declarations is a [Lease], with the IA as its "uid" (the IAID followed by the client DUID).
+fn parse_v6(input: &str) -> Result<ParserResult, String> {
+    let config = config::parse(input)?;
+    let mut leases = Leases::new();
+    let mut byte_order = None;
+
+    for statement in config.statements.iter() {
+        match statement {
+            Statement::Parameter(p) if p.name == "authoring-byte-order" => byte_order = p.values.first().cloned(),
+            Statement::Block(ia) if matches!(ia.words.first().map(|w| w.as_str()), Some("ia-na" | "ia-ta" | "ia-pd")) => {
+                for statement in ia.statements.iter() {
+                    if let Statement::Block(block) = statement {
+                        if matches!(block.words.first().map(|w| w.as_str()), Some("iaaddr" | "iaprefix")) {
+                            let mut lease = parse_iaaddr(ia, block)?;
+                            lease.byte_order = byte_order.clone();
+                            leases.push(lease);
+                        }
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+
+    Ok(ParserResult::new(leases))
+}
+
+/// Options of [parse_with].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseOptions {
+    /// The dialect of the leases file
+    pub dialect: Dialect,
+    /// Reject the statements which are not valid for the dialect, see [parse_with]
+    pub strict: bool,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        ParseOptions { dialect: Dialect::Isc, strict: false }
+    }
+}
+
+/// Parse the contents of a leases file of the given [Dialect].
+///
+/// Without `strict`, the leases are parsed with the union of the grammars of the dialects, the
+/// same as [parse] (or as the DHCPv6 leases for [Dialect::IscV6]).
+///
+/// With `strict`, each statement is first validated against the grammar of the dialect's man
+/// page, and the first invalid one is reported with its line. For example:
+///
+/// * OpenBsd: the dates require the "UTC" suffix, and there is no "binding state" nor any of the
+///   failover statements
+/// * Isc: the dates are without "UTC" (or "epoch seconds", or "never" for the end dates), the
+///   binding states are those of dhcpd, and there is no bare "abandoned" statement (an abandoned
+///   lease is "binding state abandoned")
+/// * IscV6: the "lease" declarations are replaced by the "ia-na", "ia-ta" and "ia-pd" ones
+///
+/// ```rust
+/// use dhcpd_parser::parser::{self, Dialect, ParseOptions};
+///
+/// let options = ParseOptions { dialect: Dialect::OpenBsd, strict: true };
+/// let res = parser::parse_with("lease 192.168.0.2 {
+///     starts 2 2019/01/01 22:00:00 UTC;
+///     ends 2 2019/01/01 23:00:00;
+/// }", &options);
+///
+/// assert_eq!(res.err().unwrap(), "line 3: 'ends' expects 'weekday yyyy/mm/dd hh:mm:ss UTC', found '2 2019/01/01 23:00:00'");
+/// ```
+///
+/// See:
+/// * [OpenBSD leases man page](https://man.openbsd.org/dhcpd.leases.5)
+/// * [ISC leases man page](https://manpages.debian.org/testing/isc-dhcp-server/dhcpd.leases.5.en.html)
+pub fn parse_with(input: &str, options: &ParseOptions) -> Result<ParserResult, String> {
+    if options.strict {
+        validate(input, options.dialect)?;
+    }
+
+    match options.dialect {
+        Dialect::OpenBsd | Dialect::Isc => parse(input),
+        Dialect::IscV6 => parse_v6(input),
+    }
+}
+
+/// Parse the contents of a leases file of any [Dialect], as guessed by [detect_format].
+///
+/// Returns the dialect along with the leases. The DHCPv6 leases of ISC dhcpd are supported too:
+/// each address (or prefix) of an IA is a [Lease] with the IA as its "uid".
+///
+/// With `strict`, each statement must be valid for the detected dialect (see [parse_with]), i.e.,
+/// "binding state" is rejected in an OpenBSD file and "abandoned" in an ISC file.
+///
+/// ```rust
+/// use dhcpd_parser::parser::{self, Dialect};
+///
+/// let (dialect, res) = parser::parse_any("ia-na \"\\001\\000\\000\\000\\000\\001\" {
+///     cltt 2 2019/01/01 22:00:00;
+///     iaaddr 2001:db8::2 {
+///         binding state active;
+///         preferred-life 3600;
+///         max-life 7200;
+///         ends 2 2019/01/01 23:00:00;
+///     }
+/// }", true).unwrap();
+///
+/// assert_eq!(dialect, Dialect::IscV6);
+/// assert_eq!(res.leases[0].ip, "2001:db8::2");
+/// assert!(parser::parse_any("lease 192.168.0.2 { binding state active; abandoned; }", true).is_err());
+/// ```
+pub fn parse_any(input: &str, strict: bool) -> Result<(Dialect, ParserResult), String> {
+    let dialect = detect_format(input);
+    let res = parse_with(input, &ParseOptions { dialect, strict })?;
+
+    Ok((dialect, res))
+}
+
+/// Split the input at the lines starting with a "lease" declaration into the header (the text
+/// before the first lease) and chunks of roughly `target` bytes, each holding whole lease blocks.
+/// Each chunk comes with the value of the last "authoring-byte-order" statement before it, found
+/// by a sequential scan of the lines.
+#[cfg(feature = "rayon")]
+fn split_blocks(input: &str, target: usize) -> (&str, Vec<(Option<&str>, &str)>) {
+    const BYTE_ORDER: &str = "authoring-byte-order";
+
+    let mut boundaries = Vec::new();
+    let mut byte_order = None;
+    let mut offset = 0;
+
+    for line in input.split_inclusive('\n') {
+        let word = line.trim_start();
+        if word.starts_with("lease") && word[5..].starts_with(|c: char| c.is_ascii_whitespace()) {
+            boundaries.push((offset + line.len() - word.len(), byte_order));
+        } else if let Some(value) = word.strip_prefix(BYTE_ORDER) {
+            let value = value.split(|c: char| c.is_ascii_whitespace() || c == ';').find(|v| !v.is_empty());
+            byte_order = value.or(byte_order);
+        }
+        offset += line.len();
+    }
+
+    let first = match boundaries.first() {
+        Some(&(b, _)) => b,
+        None => return (input, Vec::new()),
+    };
+
+    let mut chunks = Vec::new();
+    let (mut start, mut start_order) = boundaries[0];
+    for &(b, order) in boundaries.iter() {
+        if b - start >= target {
+            chunks.push((start_order, &input[start..b]));
+            start = b;
+            start_order = order;
+        }
+    }
+    chunks.push((start_order, &input[start..]));
+
+    (&input[..first], chunks)
+}
+
+/// Parse the contents of the leases file in parallel (requires the "rayon" feature).
+///
+/// # Overview
+///
+/// The lease blocks are independent once the "authoring-byte-order" is known. The input is split
+/// at the lines starting with a "lease" declaration into chunks that are lexed and parsed
+/// concurrently on the [rayon] thread pool. The chunks are reassembled in file order, so the
+/// resulting [Leases] are the same as with [parse] (i.e., the last entry for an IP still wins).
+///
+/// The "authoring-byte-order" in effect at the start of each chunk is found by a sequential scan
+/// of the lines, so a statement placed between the lease blocks applies to the following chunks
+/// too. The statement has to be on a line of its own for the scan to find it.
+///
+/// For small inputs the overhead of splitting outweighs the gain; prefer [parse] for those.
+#[cfg(feature = "rayon")]
+pub fn parse_parallel(input: &str) -> Result<ParserResult, String> {
+    use rayon::prelude::*;
+
+    // Aim for a few chunks per thread to balance the load, but not too small to be worth it
+    let target = (input.len() / (rayon::current_num_threads() * 4)).max(64 * 1024);
+    let (header, chunks) = split_blocks(input, target);
+
+    let mut bite_order: Option<String> = None;
+    let mut leases = Leases::new();
+    for lease in parse_tokens(&lex(header)?, &mut bite_order)? {
+        leases.push(lease);
+    }
+
+    let parsed = chunks
+        .par_iter()
+        .map(|(order, chunk)| {
+            let mut bite_order = order.map(|o| o.to_owned()).or_else(|| bite_order.clone());
+            parse_tokens(&lex(*chunk)?, &mut bite_order)
+        })
+        .collect::<Result<Vec<Vec<Lease>>, String>>()?;
+
+    for lease in parsed.into_iter().flatten() {
+        leases.push(lease);
+    }
+
+    Ok(ParserResult::new(leases))
+}
+
+/// Parse the contents of the leases file into [LeaseRef] entries borrowing from `input`.
+///
+/// This is the zero-copy counterpart of [parse]: the input is tokenized by the [Lexer] without
+/// allocating for each word, and the values of each [LeaseRef] are slices of `input`. It is
+/// intended for read-only consumers that do not need to keep the leases beyond the lifetime of the
+/// loaded contents.
+///
+/// ```rust
+/// use dhcpd_parser::parser;
+///
+/// let content = String::from("lease 192.168.0.2 {
+///     starts 2 2019/01/01 22:00:00 UTC;
+///     hostname \"TESTHOSTNAME\";
+/// }");
+///
+/// let leases = parser::parse_ref(&content).unwrap();
+///
+/// assert_eq!(leases[0].ip, "192.168.0.2");
+/// assert_eq!(leases[0].hostname.as_deref(), Some("TESTHOSTNAME"));
+/// ```
+pub fn parse_ref(input: &str) -> Result<Vec<LeaseRef<'_>>, String> {
+    use crate::leases::LeaseKeyword;
+
+    let mut leases = Vec::new();
+    let mut bite_order: Option<&str> = None;
+
+    let mut it = Lexer::new(input).peekable();
+
+    while let Some(token) = it.next() {
+        match token.kind {
+            TokenKind::Decl(ConfigKeyword::Lease) => {
+                let mut lease = LeaseRef::new(next_text(&mut it, "IP address")?);
+
+                match it.next() {
+                    Some(Token { kind: TokenKind::Paren('{'), .. }) => (),
+                    other => return Err(format!("Expected '{{', got '{:?}'", other)),
+                }
+
+                parse_lease_ref(&mut lease, &mut it)?;
+
+                // Set the bite_order if supplied (Linux only)
+                lease.byte_order = bite_order;
+                leases.push(lease);
+            }
+            TokenKind::Opt(LeaseKeyword::BiteOrder) => {
+                bite_order = Some(next_text(&mut it, "author-bite-order value")?);
+                match it.next() {
+                    Some(Token { kind: TokenKind::Endl, .. }) => (),
+                    _ => return Err("Expected semicolon after author-bite-order term".to_owned()),
+                }
+            }
+            TokenKind::Opt(LeaseKeyword::Ignored) => {
+                // Consume up to the endl
+                while it.next_if(|k| k.kind != TokenKind::Endl && k.kind != TokenKind::Paren('}')).is_some() {}
+                it.next();
+            }
+            _ => {
+                return Err(format!("Unexpected {:?}", token));
+            }
+        }
+    }
+
+    Ok(leases)
+}
+
+/// Error returned by the [LeaseReader] iterator.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    /// The line number (1-based) where the failing statement ended.
+    pub line: usize,
+    /// Description of the error.
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Streaming parser reading the leases from a [BufRead] source.
+///
+/// # Overview
+///
+/// Unlike [parse], which lexes the whole input and collects all the [Lease] entries before
+/// returning, the reader lexes the input one line at a time and yields each [Lease] as soon as
+/// the closing '}' of its block is seen. Only the tokens of the statement currently being read
+/// are retained, so the memory used does not grow with the size of the leases file.
+///
+/// The bytes that are not valid UTF-8 are escaped as by [parse_file]. The reader stops (i.e.,
+/// returns `None`) after the first error.
+///
+/// # Example
+///
+/// ```rust
+/// use std::io::Cursor;
+/// use dhcpd_parser::parser::LeaseReader;
+///
+/// let content = "
+///     lease 192.168.0.2 {
+///         starts 2 2019/01/01 22:00:00 UTC;
+///         ends 2 2019/01/01 23:00:00 UTC;
+///     }
+///     lease 192.168.0.3 {
+///         starts 2 2019/01/01 22:00:00 UTC;
+///     }";
+///
+/// let reader = LeaseReader::new(Cursor::new(content));
+/// let ips: Vec<String> = reader.map(|l| l.unwrap().ip).collect();
+///
+/// assert_eq!(ips, vec!["192.168.0.2", "192.168.0.3"]);
+/// ```
+pub struct LeaseReader<R: BufRead> {
+    reader: R,
+    /// The current line (1-based) of the input.
+    line: usize,
+    /// Tokens of the top-level statement not yet completed.
+    pending: Vec<LexItem>,
+    /// Completed top-level statements waiting to be parsed.
+    statements: VecDeque<Vec<LexItem>>,
+    /// Nesting depth of the braces in `pending`.
+    depth: usize,
+    /// The value of the last "authoring-byte-order" statement.
+    bite_order: Option<String>,
+    done: bool,
+}
+
+impl<R: BufRead> LeaseReader<R> {
+    /// Create a new reader over the contents of a leases file.
+    pub fn new(reader: R) -> LeaseReader<R> {
+        LeaseReader {
+            reader,
+            line: 0,
+            pending: Vec::new(),
+            statements: VecDeque::new(),
+            depth: 0,
+            bite_order: None,
+            done: false,
+        }
+    }
+
+    fn error<S: Into<String>>(&mut self, message: S) -> ParseError {
+        self.done = true;
+        ParseError { line: self.line, message: message.into() }
+    }
+
+    /// Read and lex the next line, moving any completed top-level statements into `statements`.
+    /// Returns false at the end of the input.
+    /// The line is decoded as [parse_file] does, with the bytes that are not valid UTF-8 escaped.
+    fn read_line(&mut self) -> Result<bool, ParseError> {
+        let mut buf = Vec::new();
+        match self.reader.read_until(b'\n', &mut buf) {
+            Ok(0) => return Ok(false),
+            Ok(_) => self.line += 1,
+            Err(e) => {
+                self.line += 1;
+                return Err(self.error(format!("Failed to read input: {}", e)));
+            }
+        }
+
+        let tokens = lex(decode_bytes(&buf)).map_err(|e| self.error(e))?;
+        for token in tokens {
+            let complete = match &token {
+                LexItem::Paren('{') => {
+                    self.depth += 1;
+                    false
+                }
+                LexItem::Paren('}') => {
+                    self.depth = self.depth.saturating_sub(1);
+                    self.depth == 0
+                }
+                LexItem::Endl => self.depth == 0,
+                _ => false,
+            };
+
+            self.pending.push(token);
+            if complete {
+                self.statements.push_back(std::mem::take(&mut self.pending));
+            }
+        }
+
+        Ok(true)
+    }
+}
+
+impl<R: BufRead> Iterator for LeaseReader<R> {
+    type Item = Result<Lease, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            if let Some(tokens) = self.statements.pop_front() {
+                let mut it = tokens.iter().peekable();
+                let res = parse_statement(&mut it, &mut self.bite_order);
+
+                // Keep any tokens following the statement for the next call
+                let rest: Vec<LexItem> = it.cloned().collect();
+                if !rest.is_empty() {
+                    self.statements.push_front(rest);
+                }
+
+                match res {
+                    Ok(Some(lease)) => return Some(Ok(lease)),
+                    Ok(None) => continue,
+                    Err(e) => return Some(Err(self.error(e))),
+                }
+            }
+
+            match self.read_line() {
+                Ok(true) => (),
+                Ok(false) => {
+                    self.done = true;
+                    if !self.pending.is_empty() {
+                        return Some(Err(ParseError {
+                            line: self.line,
+                            message: format!("Unexpected end of input, got '{:?}'", self.pending.last()),
+                        }));
+                    }
+                }
+                Err(e) => return Some(Err(e)),
+            }
+        }
+
+        None
+    }
+}
@@ -0,0 +1,209 @@
+//! Anonymisation of the leases, to share a leases file (i.e., in a bug report) without the
+//! identities of the clients (requires the "redact" feature).
+//!
+//! The [Redactor] replaces the MAC addresses, the uids, the hostnames and optionally the IPs
+//! with pseudonyms. Each pseudonym is derived from the value with a keyed HMAC-SHA256, so:
+//!
+//! * A value gets the same pseudonym in every file redacted with the same key, and the
+//!   redacted files can still be compared with each other
+//! * Without the key, the pseudonyms can not be traced back to the values (i.e., by hashing
+//!   all of the MAC addresses of a vendor)
+//!
+//! The pseudonyms keep the format of the values: a MAC address is replaced by a MAC address of
+//! the same length, a uid holding the MAC address of the client (type 1) by the uid of the
+//! pseudonym of that MAC address, and the IPs are shuffled within their address family with no
+//! two addresses mapped to the same pseudonym. The redacted leases are written back to a leases
+//! file with [write_leases](crate::export::write_leases).
+//!
+//! ```rust
+//! use dhcpd_parser::export::write_leases;
+//! use dhcpd_parser::parser::{self, Dialect};
+//! use dhcpd_parser::redact::Redactor;
+//!
+//! let leases = parser::parse_file("tests/data/dhcpd-linux.leases").unwrap().leases;
+//! let redacted = Redactor::new(b"secret key").keep_oui(true).redact(&leases);
+//!
+//! let mac = &redacted[0].hardware.as_ref().unwrap().mac;
+//! assert!(mac.starts_with("00:ab:d4:"));
+//! assert_ne!(mac, "00:ab:d4:41:21:10");
+//!
+//! let mut file = Vec::new();
+//! write_leases(&redacted, Dialect::Isc, &mut file).unwrap();
+//! let reloaded = parser::parse(String::from_utf8(file).unwrap()).unwrap().leases;
+//! assert_eq!(reloaded, redacted);
+//! ```
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use crate::formats::kea::client_id;
+use crate::leases::{Lease, Leases, LeasesMethods};
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// The number of rounds of the Feistel network shuffling the IPs.
+const ROUNDS: u8 = 4;
+
+/// Format the bytes as lowercase hexadecimal separated by ':' (i.e., "00:11:22").
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect::<Vec<String>>().join(":")
+}
+
+/// Parse the bytes of a value in hexadecimal separated by ':', such as a MAC address.
+fn from_hex(value: &str) -> Option<Vec<u8>> {
+    value
+        .split(':')
+        .map(|b| if b.is_empty() || b.len() > 2 { None } else { u8::from_str_radix(b, 16).ok() })
+        .collect()
+}
+
+/// Builder replacing the identities of the clients in the leases with pseudonyms.
+pub struct Redactor {
+    key: Vec<u8>,
+    keep_oui: bool,
+    ips: bool,
+}
+
+impl Redactor {
+    /// Create with the secret `key` of the HMAC. The same key gives the same pseudonyms.
+    pub fn new(key: &[u8]) -> Redactor {
+        Redactor {
+            key: key.to_vec(),
+            keep_oui: false,
+            ips: false,
+        }
+    }
+
+    /// Keep the vendor prefix (the first 3 bytes) of the MAC addresses. Otherwise, the
+    /// pseudonyms are locally administered addresses, which are never assigned to a vendor.
+    pub fn keep_oui(&mut self, keep: bool) -> &mut Self {
+        self.keep_oui = keep;
+        self
+    }
+
+    /// Replace the IPs of the leases too.
+    pub fn ips(&mut self, redact: bool) -> &mut Self {
+        self.ips = redact;
+        self
+    }
+
+    /// At least `len` bytes of HMAC for the value, separated by `kind` from the other kinds of values.
+    fn digest(&self, kind: &str, value: &[u8], len: usize) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(len);
+        let mut counter: u8 = 0;
+
+        while bytes.len() < len {
+            let mut mac = HmacSha256::new_from_slice(&self.key).expect("HMAC accepts keys of any length");
+            mac.update(kind.as_bytes());
+            mac.update(&[0, counter]);
+            mac.update(value);
+            bytes.extend_from_slice(&mac.finalize().into_bytes());
+            counter = counter.wrapping_add(1);
+        }
+
+        bytes.truncate(len);
+        bytes
+    }
+
+    /// The pseudonym of a MAC address, of the same length. A value that is not a MAC address
+    /// gets the pseudonym of an Ethernet address.
+    pub fn mac(&self, mac: &str) -> String {
+        let original = from_hex(mac).unwrap_or_default();
+        let mut bytes = self.digest("mac", mac.to_lowercase().as_bytes(), original.len().max(6));
+
+        if self.keep_oui && original.len() >= 3 {
+            bytes[..3].copy_from_slice(&original[..3]);
+        } else {
+            // Locally administered, unicast
+            bytes[0] = (bytes[0] & 0xfc) | 0x02;
+        }
+
+        hex(&bytes)
+    }
+
+    /// The pseudonym of a client identifier, in hexadecimal. An identifier made of the hardware
+    /// type 1 and a MAC address keeps the type, with the pseudonym of the MAC address.
+    pub fn uid(&self, uid: &str) -> String {
+        let bytes = from_hex(&client_id(uid)).unwrap_or_default();
+
+        match bytes.split_first() {
+            Some((1, mac)) if mac.len() == 6 => format!("01:{}", self.mac(&hex(mac))),
+            _ => hex(&self.digest("uid", &bytes, bytes.len().max(1))),
+        }
+    }
+
+    /// The pseudonym of a hostname, i.e., "host-1a2b3c4d". The domain is replaced too.
+    pub fn hostname(&self, hostname: &str) -> String {
+        let bytes = self.digest("hostname", hostname.to_lowercase().as_bytes(), 4);
+        format!("host-{}", hex(&bytes).replace(':', ""))
+    }
+
+    /// Shuffle the `bits` low bits of the value with a Feistel network, which maps each value to
+    /// a distinct pseudonym. For an odd number of bits, the halves differ by one bit and swap
+    /// their sizes at each round.
+    fn shuffle(&self, value: u128, bits: u32) -> u128 {
+        let mask = |bits: u32| u128::MAX.checked_shr(128 - bits).unwrap_or(0);
+        let (mut left_bits, mut right_bits) = (bits - bits / 2, bits / 2);
+        let (mut left, mut right) = (value >> right_bits & mask(left_bits), value & mask(right_bits));
+
+        for round in 0..ROUNDS {
+            let mut input = vec![round, bits as u8];
+            input.extend_from_slice(&right.to_be_bytes());
+            let f = self.digest("ip", &input, 16).iter().fold(0u128, |acc, b| acc << 8 | *b as u128);
+            let next = left ^ (f & mask(left_bits));
+            left = right;
+            right = next;
+            std::mem::swap(&mut left_bits, &mut right_bits);
+        }
+
+        left.checked_shl(right_bits).unwrap_or(0) | right
+    }
+
+    /// The pseudonym of an IPv4 or IPv6 address, or of an IPv6 prefix ("2001:db8::/56", keeping
+    /// the length; only the bits of the prefix are shuffled, so distinct prefixes keep distinct
+    /// pseudonyms). Other values are returned as-is.
+    pub fn ip(&self, ip: &str) -> String {
+        if let Some((address, len)) = ip.split_once('/') {
+            if let (Ok(address), Ok(len)) = (address.parse::<Ipv6Addr>(), len.parse::<u32>()) {
+                let host_bits = 128 - len.min(128);
+                let prefix = u128::from(address).checked_shr(host_bits).unwrap_or(0);
+                let shuffled = self.shuffle(prefix, 128 - host_bits).checked_shl(host_bits).unwrap_or(0);
+                return format!("{}/{}", Ipv6Addr::from(shuffled), len);
+            }
+        } else if let Ok(address) = ip.parse::<Ipv4Addr>() {
+            return Ipv4Addr::from(self.shuffle(u32::from(address) as u128, 32) as u32).to_string();
+        } else if let Ok(address) = ip.parse::<Ipv6Addr>() {
+            return Ipv6Addr::from(self.shuffle(u128::from(address), 128)).to_string();
+        }
+
+        ip.to_owned()
+    }
+
+    /// Redact a lease. The dates and the binding states are kept.
+    pub fn lease(&self, lease: &Lease) -> Lease {
+        let mut redacted = lease.clone();
+
+        if let Some(hardware) = redacted.hardware.as_mut() {
+            hardware.mac = self.mac(&hardware.mac);
+        }
+        redacted.uid = lease.uid.as_deref().map(|uid| self.uid(uid));
+        redacted.client_hostname = lease.client_hostname.as_deref().map(|h| self.hostname(h));
+        redacted.hostname = lease.hostname.as_deref().map(|h| self.hostname(h));
+        if self.ips {
+            redacted.ip = self.ip(&lease.ip);
+        }
+
+        redacted
+    }
+
+    /// Redact all of the leases, in the same order.
+    pub fn redact(&self, leases: &Leases) -> Leases {
+        let mut redacted = Leases::new();
+        for ndx in 0..leases.count() {
+            redacted.push(self.lease(&leases[ndx]));
+        }
+
+        redacted
+    }
+}
@@ -0,0 +1,273 @@
+//! Analytics of the lease lifetimes and of the renewals of the clients, to tune the lease times.
+//!
+//! dhcpd appends an entry to the leases file each time a lease changes, so the file is a journal
+//! of the grants to each client (identified by its MAC address, or its uid). A grant is an entry
+//! with a new "starts" date; the later entries for the same grant (i.e., when the lease expires)
+//! are skipped. From the grants, [RenewalAnalyzer::report] computes:
+//!
+//! * The granted durations ("ends" minus "starts")
+//! * The time between the renewals: the transaction times ("cltt", or "starts" if missing) of the
+//!   successive grants to a client, when the grant came before the end of the previous one
+//! * The fraction of the previous lease elapsed at each renewal (about 0.5 when the clients renew
+//!   at T1)
+//! * The clients that never renewed a lease before it ended
+//!
+//! The distributions are computed overall, per client and per pool.
+//!
+//! ```rust
+//! use dhcpd_parser::renewal::RenewalAnalyzer;
+//! use dhcpd_parser::parser;
+//!
+//! let leases = parser::parse_file("tests/data/dhcpd-multiple.leases").unwrap().leases;
+//! let stats = RenewalAnalyzer::new().report(&leases);
+//!
+//! let renewals = stats.overall.renewal_interval.unwrap();
+//! assert_eq!(renewals.count, 9);
+//! assert!(renewals.max < 300.0);
+//! ```
+use std::collections::{HashMap, HashSet};
+
+use chrono::prelude::*;
+
+use crate::common::IpRange;
+use crate::leases::{Lease, Leases, LeasesMethods};
+
+/// A distribution of values (in seconds, or a fraction).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Distribution {
+    pub count: usize,
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+    pub median: f64,
+    /// The 90th percentile (nearest rank)
+    pub p90: f64,
+}
+
+impl Distribution {
+    /// The distribution of the values, None if there are none.
+    pub fn of(values: &[f64]) -> Option<Distribution> {
+        if values.is_empty() {
+            return None;
+        }
+
+        let mut sorted = values.to_vec();
+        sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+        let count = sorted.len();
+        let rank = |p: f64| sorted[((p * count as f64).ceil() as usize).clamp(1, count) - 1];
+        let median = if count & 1 == 0 {
+            (sorted[count / 2 - 1] + sorted[count / 2]) / 2.0
+        } else {
+            sorted[count / 2]
+        };
+
+        Some(Distribution {
+            count,
+            min: sorted[0],
+            max: sorted[count - 1],
+            mean: sorted.iter().sum::<f64>() / count as f64,
+            median,
+            p90: rank(0.9),
+        })
+    }
+}
+
+/// The distributions of a set of grants: overall, of a client or of a pool.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct LifetimeStats {
+    /// The number of grants
+    pub grants: usize,
+    /// The number of renewals
+    pub renewals: usize,
+    /// The granted durations, in seconds
+    pub duration: Option<Distribution>,
+    /// The time between the renewals, in seconds
+    pub renewal_interval: Option<Distribution>,
+    /// The fraction of the previous lease elapsed at the renewals, from 0.0 to 1.0
+    pub elapsed_at_renewal: Option<Distribution>,
+}
+
+/// The samples collected for a [LifetimeStats].
+#[derive(Default)]
+struct Samples {
+    grants: usize,
+    durations: Vec<f64>,
+    intervals: Vec<f64>,
+    elapsed: Vec<f64>,
+}
+
+impl Samples {
+    fn stats(&self) -> LifetimeStats {
+        LifetimeStats {
+            grants: self.grants,
+            renewals: self.intervals.len(),
+            duration: Distribution::of(&self.durations),
+            renewal_interval: Distribution::of(&self.intervals),
+            elapsed_at_renewal: Distribution::of(&self.elapsed),
+        }
+    }
+}
+
+/// The statistics of a client.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClientStats {
+    /// The MAC address of the client, or its uid if there is no hardware entry
+    pub client: String,
+    pub stats: LifetimeStats,
+}
+
+/// The statistics of a pool.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PoolStats {
+    pub name: String,
+    pub ranges: Vec<IpRange>,
+    pub stats: LifetimeStats,
+}
+
+/// The result of [RenewalAnalyzer::report].
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenewalStats {
+    pub overall: LifetimeStats,
+    /// The clients, in the order of their first lease in the file
+    pub clients: Vec<ClientStats>,
+    /// The pools, in the order they were added
+    pub pools: Vec<PoolStats>,
+    /// The clients with no renewal, whose last lease has ended
+    pub never_renewed: Vec<String>,
+}
+
+/// A grant: the first entry of a lease with a new "starts" date.
+struct Grant<'a> {
+    lease: &'a Lease,
+    starts: DateTime<Utc>,
+    ends: DateTime<Utc>,
+    /// The transaction time, "cltt" or "starts"
+    at: DateTime<Utc>,
+}
+
+/// Builder for the [RenewalStats] of the leases.
+pub struct RenewalAnalyzer {
+    pools: Vec<(String, Vec<IpRange>)>,
+    at: Option<DateTime<Utc>>,
+}
+
+impl Default for RenewalAnalyzer {
+    fn default() -> Self {
+        RenewalAnalyzer::new()
+    }
+}
+
+impl RenewalAnalyzer {
+    pub fn new() -> RenewalAnalyzer {
+        RenewalAnalyzer { pools: Vec::new(), at: None }
+    }
+
+    /// Add a pool, to get its statistics in [RenewalStats::pools]. A grant (or renewal) counts
+    /// for the first pool with its IP in the ranges.
+    pub fn pool(&mut self, name: &str, ranges: &[IpRange]) -> &mut Self {
+        self.pools.push((name.to_owned(), ranges.to_vec()));
+        self
+    }
+
+    /// Evaluate the leases at the instant `dt` rather than [Utc::now()], to tell whether the
+    /// last lease of a client has ended.
+    pub fn at(&mut self, dt: DateTime<Utc>) -> &mut Self {
+        self.at = Some(dt);
+        self
+    }
+
+    fn pool_of(&self, lease: &Lease) -> Option<usize> {
+        let ip = lease.ip.parse().ok()?;
+        self.pools.iter().position(|(_, ranges)| ranges.iter().any(|r| r.contains(ip)))
+    }
+
+    /// Compute the statistics of the grants in the leases journal.
+    pub fn report(&self, leases: &Leases) -> RenewalStats {
+        let at = self.at.unwrap_or_else(Utc::now);
+
+        // The grants of each client, in the order of the file
+        let mut order: Vec<String> = Vec::new();
+        let mut grants: HashMap<String, Vec<Grant>> = HashMap::new();
+        let mut seen: HashSet<(String, &str, DateTime<Utc>)> = HashSet::new();
+        let all = leases.all();
+
+        for lease in all.iter() {
+            let client = match (&lease.hardware, &lease.uid) {
+                (Some(hw), _) => hw.mac.to_lowercase(),
+                (None, Some(uid)) => uid.clone(),
+                (None, None) => continue,
+            };
+            let (starts, ends) = match (lease.dates.starts, lease.dates.ends) {
+                (Some(starts), Some(ends)) => (starts.to_chrono(), ends.to_chrono()),
+                _ => continue,
+            };
+            if !seen.insert((client.clone(), lease.ip.as_str(), starts)) {
+                continue;
+            }
+
+            let grant = Grant { lease, starts, ends, at: lease.dates.cltt.map_or(starts, |d| d.to_chrono()) };
+            if !grants.contains_key(&client) {
+                order.push(client.clone());
+            }
+            grants.entry(client).or_default().push(grant);
+        }
+
+        let mut overall = Samples::default();
+        let mut pools: Vec<Samples> = self.pools.iter().map(|_| Samples::default()).collect();
+        let mut clients = Vec::new();
+        let mut never_renewed = Vec::new();
+
+        for client in order {
+            let mut samples = Samples::default();
+            let client_grants = &grants[&client];
+
+            for (ndx, grant) in client_grants.iter().enumerate() {
+                let pool = self.pool_of(grant.lease);
+                let duration = (grant.ends - grant.starts).num_seconds() as f64;
+
+                let renewal = ndx.checked_sub(1).map(|p| &client_grants[p]).and_then(|previous| {
+                    // A grant after the end of the previous lease is a new lease, not a renewal
+                    if grant.at < previous.starts || grant.at > previous.ends || previous.ends <= previous.starts {
+                        return None;
+                    }
+                    let interval = (grant.at - previous.at).num_seconds() as f64;
+                    let elapsed = (grant.at - previous.starts).num_seconds() as f64
+                        / (previous.ends - previous.starts).num_seconds() as f64;
+                    Some((interval, elapsed))
+                });
+
+                let targets = std::iter::once(&mut samples)
+                    .chain(std::iter::once(&mut overall))
+                    .chain(pools.iter_mut().enumerate().filter(|(p, _)| Some(*p) == pool).map(|(_, s)| s));
+                for target in targets {
+                    target.grants += 1;
+                    if duration >= 0.0 {
+                        target.durations.push(duration);
+                    }
+                    if let Some((interval, elapsed)) = renewal {
+                        target.intervals.push(interval);
+                        target.elapsed.push(elapsed);
+                    }
+                }
+            }
+
+            let ended = client_grants.last().is_some_and(|g| g.ends <= at);
+            if samples.intervals.is_empty() && ended {
+                never_renewed.push(client.clone());
+            }
+            clients.push(ClientStats { client, stats: samples.stats() });
+        }
+
+        RenewalStats {
+            overall: overall.stats(),
+            clients,
+            pools: self
+                .pools
+                .iter()
+                .zip(pools.iter())
+                .map(|((name, ranges), samples)| PoolStats { name: name.clone(), ranges: ranges.clone(), stats: samples.stats() })
+                .collect(),
+            never_renewed,
+        }
+    }
+}
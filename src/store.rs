@@ -0,0 +1,197 @@
+//! Indexed store of the effective leases, for the lookups of a long-running service.
+//!
+//! The [LeaseStore] holds the effective lease of each IP (the last entry for the IP, see
+//! [LeaseFilterBuilder::effective](crate::util::LeaseFilterBuilder::effective)), replaced in
+//! place as the entries are pushed in the order of the leases file. It maintains:
+//!
+//! * Hash indexes on the IP, the MAC address, the uid and the hostnames ("hostname" and
+//!   "client-hostname"), for a constant-time lookup of the effective lease of a key
+//! * A B-tree on the "ends" date, for the range queries on the expiry
+//!
+//! A MAC address, uid or hostname can be on the effective leases of several IPs (i.e., a client
+//! moved to another pool); the lookup returns the one pushed last. The lookups do not tell
+//! whether the lease is active, see [lease_state](crate::metrics::lease_state).
+//!
+//! ```rust
+//! use dhcpd_parser::common::Date;
+//! use dhcpd_parser::parser;
+//! use dhcpd_parser::store::LeaseStore;
+//!
+//! let leases = parser::parse_file("tests/data/dhcpd-multiple.leases").unwrap().leases;
+//! let store = LeaseStore::from_leases(&leases);
+//!
+//! let lease = store.by_mac("00:EA:D4:39:0D:04").unwrap();
+//! assert_eq!(lease.ip, "192.168.4.108");
+//! assert_eq!(store.by_hostname("clsomimx6").unwrap().ip, "192.168.4.108");
+//!
+//! let from = Date::from("1", "2022/01/10", "00:00:00").unwrap();
+//! assert_eq!(store.ending_between(from..).count(), 2);
+//! ```
+use std::collections::{BTreeMap, HashMap};
+use std::ops::RangeBounds;
+
+use crate::common::Date;
+use crate::leases::{Lease, Leases, LeasesMethods};
+
+/// The keys of a lease in the hash indexes, other than the IP.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Key {
+    Mac,
+    Uid,
+    Hostname,
+}
+
+/// The keys of the lease, normalized: the MAC address and the hostnames are lowercase.
+fn keys(lease: &Lease) -> Vec<(Key, String)> {
+    let mut keys = Vec::new();
+
+    if let Some(hw) = &lease.hardware {
+        keys.push((Key::Mac, hw.mac.to_lowercase()));
+    }
+    if let Some(uid) = &lease.uid {
+        keys.push((Key::Uid, uid.clone()));
+    }
+    for hostname in [&lease.hostname, &lease.client_hostname].iter().copied().flatten() {
+        let hostname = (Key::Hostname, hostname.to_lowercase());
+        if !keys.contains(&hostname) {
+            keys.push(hostname);
+        }
+    }
+
+    keys
+}
+
+/// The effective leases, indexed by IP, MAC address, uid, hostname and "ends" date.
+#[derive(Debug, Clone, Default)]
+pub struct LeaseStore {
+    /// The effective lease of each IP, in the order the IPs were first seen
+    leases: Vec<Lease>,
+    by_ip: HashMap<String, usize>,
+    /// The slots with the key, from the least to the most recently pushed
+    by_key: HashMap<(Key, String), Vec<usize>>,
+    by_ends: BTreeMap<Date, Vec<usize>>,
+}
+
+impl LeaseStore {
+    pub fn new() -> LeaseStore {
+        LeaseStore::default()
+    }
+
+    /// Create with the leases, in the order of the file.
+    pub fn from_leases(leases: &Leases) -> LeaseStore {
+        let mut store = LeaseStore::new();
+        store.extend(leases);
+        store
+    }
+
+    /// Push the leases, in the order of the file.
+    pub fn extend(&mut self, leases: &Leases) {
+        for ndx in 0..leases.count() {
+            self.push(leases[ndx].clone());
+        }
+    }
+
+    /// Push the next entry of the leases file, which replaces the effective lease of its IP.
+    /// Returns the replaced lease.
+    pub fn push(&mut self, lease: Lease) -> Option<Lease> {
+        let slot = match self.by_ip.get(&lease.ip) {
+            Some(slot) => *slot,
+            None => {
+                self.by_ip.insert(lease.ip.clone(), self.leases.len());
+                self.leases.push(lease);
+                self.index(self.leases.len() - 1);
+                return None;
+            }
+        };
+
+        self.unindex(slot);
+        let replaced = std::mem::replace(&mut self.leases[slot], lease);
+        self.index(slot);
+        Some(replaced)
+    }
+
+    fn index(&mut self, slot: usize) {
+        let lease = &self.leases[slot];
+        for key in keys(lease) {
+            self.by_key.entry(key).or_default().push(slot);
+        }
+        if let Some(ends) = lease.dates.ends {
+            self.by_ends.entry(ends).or_default().push(slot);
+        }
+    }
+
+    fn unindex(&mut self, slot: usize) {
+        let lease = &self.leases[slot];
+        for key in keys(lease) {
+            if let Some(slots) = self.by_key.get_mut(&key) {
+                slots.retain(|s| *s != slot);
+                if slots.is_empty() {
+                    self.by_key.remove(&key);
+                }
+            }
+        }
+        if let Some(ends) = lease.dates.ends {
+            if let Some(slots) = self.by_ends.get_mut(&ends) {
+                slots.retain(|s| *s != slot);
+                if slots.is_empty() {
+                    self.by_ends.remove(&ends);
+                }
+            }
+        }
+    }
+
+    fn by(&self, key: Key, value: String) -> Option<&Lease> {
+        let slot = self.by_key.get(&(key, value))?.last()?;
+        Some(&self.leases[*slot])
+    }
+
+    /// The effective lease of the IP.
+    pub fn by_ip(&self, ip: &str) -> Option<&Lease> {
+        self.by_ip.get(ip).map(|slot| &self.leases[*slot])
+    }
+
+    /// The last effective lease of the MAC address, case-insensitive.
+    pub fn by_mac(&self, mac: &str) -> Option<&Lease> {
+        self.by(Key::Mac, mac.to_lowercase())
+    }
+
+    /// The last effective lease of the uid, as written in the leases file.
+    pub fn by_uid(&self, uid: &str) -> Option<&Lease> {
+        self.by(Key::Uid, uid.to_owned())
+    }
+
+    /// The last effective lease with the "hostname" or the "client-hostname", case-insensitive.
+    pub fn by_hostname(&self, hostname: &str) -> Option<&Lease> {
+        self.by(Key::Hostname, hostname.to_lowercase())
+    }
+
+    /// The effective leases with their "ends" date within the range, i.e., `..at` for the
+    /// leases ended before `at`, by ascending "ends" date.
+    pub fn ending_between<R: RangeBounds<Date>>(&self, range: R) -> impl Iterator<Item = &Lease> + '_ {
+        self.by_ends.range(range).flat_map(move |(_, slots)| slots.iter().map(move |slot| &self.leases[*slot]))
+    }
+
+    /// The effective leases, in the order their IPs were first seen.
+    pub fn iter(&self) -> impl Iterator<Item = &Lease> + '_ {
+        self.leases.iter()
+    }
+
+    /// The effective leases as [Leases], i.e., to write them with
+    /// [write_leases](crate::export::write_leases).
+    pub fn to_leases(&self) -> Leases {
+        let mut leases = Leases::new();
+        for lease in self.leases.iter() {
+            leases.push(lease.clone());
+        }
+        leases
+    }
+
+    /// The number of IPs.
+    pub fn len(&self) -> usize {
+        self.leases.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.leases.is_empty()
+    }
+}
@@ -6,7 +6,10 @@
 use crate::leases::{Lease, Leases};
 #[doc(inline)]
 use crate::leases::{LeasesMethods};
+#[cfg(feature = "oui")]
+use crate::oui::OuiDb;
 use chrono::prelude::*;
+use std::collections::{HashMap, HashSet};
 
 
 pub struct LeasesFilter {}
@@ -141,7 +144,7 @@ impl LeaseFilterBuilder {
         for ndx in self.match_indexes.iter() {
             let lease = &self.leases[*ndx];
 
-            if lease.client().starts_with(&mac) {
+            if lease.hardware.as_ref().is_some_and(|hw| hw.mac.starts_with(mac)) {
                 keep_ndx.push(ndx.clone());
             }
         }
@@ -151,6 +154,28 @@ impl LeaseFilterBuilder {
         self
     }
 
+    /// Add filtering on the vendor of the MAC address in the IEEE registry (requires the "oui"
+    /// feature). The lease matches if the name of its vendor contains `vendor` (ignoring case),
+    /// so "apple" matches "Apple, Inc.", whichever of the vendor's address blocks the MAC address
+    /// is in. Unlike [on_mac](Self::on_mac), which compares the text of the MAC address with a
+    /// prefix, the vendor is looked up with [OuiDb::vendor]: the longest registered prefix, with
+    /// the MAC address in any notation (i.e., "0:3:93:1:2:3" or "00-03-93-01-02-03").
+    #[cfg(feature = "oui")]
+    pub fn on_vendor(&mut self, db: &OuiDb, vendor: &str) -> &mut Self {
+        let leases = &self.leases;
+        let vendor = vendor.to_lowercase();
+
+        self.match_indexes.retain(|&ndx| {
+            leases[ndx]
+                .hardware
+                .as_ref()
+                .and_then(|hw| hw.vendor(db))
+                .is_some_and(|v| v.to_lowercase().contains(&vendor))
+        });
+
+        self
+    }
+
     /// Add filtering on [is_active](Lease::is_active). This will inspect both the [abandoned](Lease::abandoned)
     /// and the [binding_state](Lease::binding_state) field, if specified (Linux-only).
     pub fn on_active(&mut self) -> &mut Self {
@@ -192,6 +217,46 @@ impl LeaseFilterBuilder {
         self
     }
 
+    /// Add filtering on [is_bound_at](Lease::is_bound_at) with the DateTime set to `dt` if supplied,
+    /// or [Utc::now()] if not supplied. Unlike [on_active_now](Self::on_active_now), this also
+    /// checks the "starts" date, the [abandoned](Lease::abandoned) flag and the binding state.
+    pub fn on_active_at(&mut self, dt: Option<DateTime<Utc>>) -> &mut Self {
+        let compare_dt = dt.unwrap_or_else(Utc::now);
+        let leases = &self.leases;
+
+        self.match_indexes.retain(|&ndx| leases[ndx].is_bound_at(compare_dt));
+
+        self
+    }
+
+    /// Add filtering on a hostname. The lease matches if either its [hostname](Lease::hostname)
+    /// or its [client_hostname](Lease::client_hostname) is equal to `hostname` (ignoring case).
+    pub fn on_hostname(&mut self, hostname: &str) -> &mut Self {
+        let leases = &self.leases;
+        let matches = |h: &Option<String>| h.as_ref().is_some_and(|h| h.eq_ignore_ascii_case(hostname));
+
+        self.match_indexes
+            .retain(|&ndx| matches(&leases[ndx].hostname) || matches(&leases[ndx].client_hostname));
+
+        self
+    }
+
+    /// Add a filter to only the "effective" lease items: the last entry for each IP in the file.
+    /// dhcpd appends the updated lease to the end of the file each time it changes, so the last
+    /// entry for an IP is the one currently in effect. This considers all of the leases, not only
+    /// the ones matching the preceding filters; it is usually the first filter to apply.
+    pub fn effective(&mut self) -> &mut Self {
+        let mut last: HashMap<&str, usize> = HashMap::new();
+        for (ndx, lease) in self.leases.iter().enumerate() {
+            last.insert(lease.ip.as_str(), ndx);
+        }
+
+        let keep: HashSet<usize> = last.into_values().collect();
+        self.match_indexes.retain(|ndx| keep.contains(ndx));
+
+        self
+    }
+
     /// Add a filter to only the "latest". This is interpreted as the lease item that has the
     /// latest "ends" DateTime. This is necessary because the Linux/ISC leases file will contain
     /// multiple "leases" entries for the same client/IP with overlapping DTS ranges. Why? /shrug.
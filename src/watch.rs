@@ -0,0 +1,110 @@
+//! Watching of the leases file for changes (requires the "tokio" feature).
+//!
+//! * [watch]: Stream of [Leases] snapshots, loaded each time the file changes
+//! * [watch_debounced]: Same as [watch], with a custom debounce delay
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+
+use crate::leases::Leases;
+use crate::parser::parse_file_async;
+
+/// The delay used by [watch] to wait for the file to settle after a change.
+pub const DEFAULT_DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// Watch the leases file at `path`, returning a stream of [Leases] snapshots.
+///
+/// See [watch_debounced] for the details. This uses the [DEFAULT_DEBOUNCE] delay.
+pub fn watch<P: AsRef<Path>>(path: P) -> Result<ReceiverStream<Result<Leases, String>>, String> {
+    watch_debounced(path, DEFAULT_DEBOUNCE)
+}
+
+/// Watch the leases file at `path`, returning a stream of [Leases] snapshots.
+///
+/// # Overview
+///
+/// The stream yields the leases loaded from the file when it is first polled, and then each time
+/// the file changes. The file is loaded with [parse_file_async], so a failure to load or parse the
+/// file is yielded as an error item and the watching continues.
+///
+/// The file system events come from the native watcher of the platform (i.e., inotify on Linux).
+/// Rather than the file itself, the parent directory is watched: dhcpd rewrites the leases file by
+/// creating a new file and renaming it over the old one, which replaces the watched inode. The
+/// events of a rewrite arrive in bursts, so the file is only loaded once no more events have
+/// been seen for the `debounce` delay.
+///
+/// The watching stops when the stream is dropped. This must be called within a [tokio] runtime.
+///
+/// ```rust,no_run
+/// use dhcpd_parser::leases::LeasesMethods;
+/// use dhcpd_parser::watch;
+/// use tokio_stream::StreamExt;
+///
+/// # async fn run() -> Result<(), String> {
+/// let mut snapshots = watch::watch("/var/lib/dhcp/dhcpd.leases")?;
+///
+/// while let Some(leases) = snapshots.next().await {
+///     println!("Loaded {} leases", leases?.count());
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub fn watch_debounced<P: AsRef<Path>>(
+    path: P,
+    debounce: Duration,
+) -> Result<ReceiverStream<Result<Leases, String>>, String> {
+    let path = path.as_ref().to_path_buf();
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| format!("Not a file path: {}", path.display()))?
+        .to_owned();
+    let dir = match path.parent() {
+        Some(p) if !p.as_os_str().is_empty() => p.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+
+    // The watcher calls back from its own thread; forward the relevant events to the task
+    let (event_tx, mut event_rx) = mpsc::unbounded_channel();
+    let mut watcher = RecommendedWatcher::new(
+        move |res: notify::Result<notify::Event>| {
+            if let Ok(event) = res {
+                if event.paths.iter().any(|p| p.file_name() == Some(&file_name)) {
+                    let _ = event_tx.send(());
+                }
+            }
+        },
+        notify::Config::default(),
+    )
+    .map_err(|e| format!("Failed to watch: {}. Error: {}", dir.display(), e))?;
+    watcher
+        .watch(&dir, RecursiveMode::NonRecursive)
+        .map_err(|e| format!("Failed to watch: {}. Error: {}", dir.display(), e))?;
+
+    let (tx, rx) = mpsc::channel(1);
+    tokio::spawn(async move {
+        // Keep the watcher alive for as long as the task runs
+        let _watcher = watcher;
+
+        loop {
+            let snapshot = parse_file_async(&path).await.map(|res| res.leases);
+            if tx.send(snapshot).await.is_err() {
+                // The stream was dropped
+                return;
+            }
+
+            // Wait for a change, then until the file has settled
+            tokio::select! {
+                _ = tx.closed() => return,
+                event = event_rx.recv() => if event.is_none() {
+                    return;
+                },
+            }
+            while let Ok(Some(_)) = tokio::time::timeout(debounce, event_rx.recv()).await {}
+        }
+    });
+
+    Ok(ReceiverStream::new(rx))
+}
@@ -0,0 +1,181 @@
+#![cfg(feature = "cli")]
+
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::process::{Child, Command, Stdio};
+use std::thread;
+use std::time::Duration;
+
+fn run(args: &[&str]) -> (bool, String) {
+    let output = Command::new(env!("CARGO_BIN_EXE_dhcpd-leases"))
+        .args(args)
+        .output()
+        .unwrap();
+
+    (output.status.success(), String::from_utf8(output.stdout).unwrap())
+}
+
+#[test]
+fn list_test() {
+    let (ok, out) = run(&["-f", "tests/data/dhcpd-multiple.leases", "list", "--columns", "ip,mac"]);
+
+    assert!(ok);
+    let lines: Vec<&str> = out.lines().collect();
+    assert_eq!(lines.len(), 17);
+    assert_eq!(lines[0], "IP             MAC");
+    assert_eq!(lines[1], "192.168.4.101  00:ea:d4:41:21:10");
+
+    let (ok, _) = run(&["-f", "tests/data/dhcpd-multiple.leases", "list", "--columns", "ip,unknown"]);
+    assert!(!ok);
+}
+
+#[test]
+fn active_at_test() {
+    let (ok, out) = run(&[
+        "-f", "tests/data/dhcpd-multiple.leases",
+        "--at", "2022/01/11 00:07:10",
+        "active", "--columns", "ip,mac",
+    ]);
+
+    assert!(ok);
+    assert_eq!(out, "IP             MAC\n192.168.4.108  00:ea:d4:39:0d:04\n");
+}
+
+#[test]
+fn lookup_test() {
+    let lookup = |key: &str| {
+        let (ok, out) = run(&["-f", "tests/data/dhcpd-multiple.leases", "lookup", key]);
+        assert!(ok);
+        out.lines().skip(1).map(|l| l.split_whitespace().next().unwrap().to_owned()).collect::<Vec<String>>()
+    };
+
+    // Only the effective leases
+    assert_eq!(lookup("192.168.4.106"), vec!["192.168.4.106"]);
+    assert_eq!(lookup("00:EA:D4:39:0D:04"), vec!["192.168.4.107", "192.168.4.105", "192.168.4.108"]);
+    assert_eq!(lookup("clsomimx6"), vec!["192.168.4.106", "192.168.4.108"]);
+}
+
+#[test]
+fn history_test() {
+    let (ok, out) = run(&["-f", "tests/data/dhcpd-multiple.leases", "history", "192.168.4.106"]);
+
+    assert!(ok);
+    assert_eq!(out.lines().count(), 4);
+}
+
+#[test]
+fn validate_test() {
+    let (ok, out) = run(&["-f", "tests/data/dhcpd-bsd.leases", "validate"]);
+    assert!(ok);
+    assert_eq!(out, "tests/data/dhcpd-bsd.leases: OK, 3 leases\n");
+
+    let (ok, _) = run(&["-f", "tests/data/missing.leases", "validate"]);
+    assert!(!ok);
+}
+
+#[test]
+fn stats_test() {
+    let (ok, out) = run(&["-f", "tests/data/dhcpd-multiple.leases", "--at", "2022/01/11 00:07:10", "stats"]);
+
+    assert!(ok);
+    assert!(out.contains("Entries:          16\n"));
+    assert!(out.contains("Distinct IPs:     6\n"));
+    assert!(out.contains("Distinct MACs:    4\n"));
+    assert!(out.contains("  active:         1\n  free:           5\n"));
+    assert!(out.contains("Active at 2022/01/11 00:07:10: 1\n"));
+}
+
+#[test]
+fn export_test() {
+    let (ok, out) = run(&["-f", "tests/data/dhcpd-linux.leases", "export", "--format", "json"]);
+    assert!(ok);
+    let json: serde_json::Value = serde_json::from_str(&out).unwrap();
+    assert_eq!(json.as_array().unwrap().len(), 6);
+    assert_eq!(json[0]["ip"], "192.168.4.101");
+    assert_eq!(json[0]["hardware"]["mac"], "00:ab:d4:41:21:10");
+
+    let (ok, out) = run(&["-f", "tests/data/dhcpd-linux.leases", "export", "--format", "csv", "--columns", "ip,mac"]);
+    assert!(ok);
+    let lines: Vec<&str> = out.lines().collect();
+    assert_eq!(lines.len(), 7);
+    assert_eq!(lines[0], "ip,mac");
+    assert_eq!(lines[1], "192.168.4.101,00:ab:d4:41:21:10");
+
+    let (ok, out) = run(&["-f", "tests/data/dhcpd-multiple.leases", "export", "--format", "ndjson", "--effective"]);
+    assert!(ok);
+    assert_eq!(out.lines().count(), 6);
+}
+
+#[test]
+fn diff_test() {
+    let (ok, out) = run(&["diff", "tests/data/dhcpd-bsd.leases", "tests/data/dhcpd-linux.leases"]);
+    assert!(ok);
+    let lines: Vec<&str> = out.lines().collect();
+    assert_eq!(lines.len(), 8);
+    assert_eq!(lines[0], "- 192.168.0.2 11:11:11:11:11:11");
+    assert_eq!(lines[2], "+ 192.168.4.101 00:ab:d4:41:21:10");
+
+    let (ok, out) = run(&["diff", "tests/data/dhcpd-linux.leases", "tests/data/dhcpd-multiple.leases"]);
+    assert!(ok);
+    assert!(out.contains("~ 192.168.4.101 mac: 00:ab:d4:41:21:10 -> 00:ea:d4:41:21:10\n"));
+    assert!(out.contains("~ 192.168.4.108 ends: 2022-01-08T17:56:16Z -> 2022-01-11T00:13:17Z\n"));
+
+    let (ok, out) = run(&["diff", "tests/data/dhcpd-linux.leases", "tests/data/dhcpd-linux.leases"]);
+    assert!(ok);
+    assert_eq!(out, "");
+}
+
+/// Send an HTTP request for the path and return the response.
+fn scrape(addr: &str, path: &str) -> String {
+    let mut stream = TcpStream::connect(addr).unwrap();
+    stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+    write!(stream, "GET {} HTTP/1.1\r\nHost: {}\r\nAccept: */*\r\n\r\n", path, addr).unwrap();
+
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    response
+}
+
+/// Kills the server when the test ends, even on failure.
+struct Server(Child);
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+    }
+}
+
+#[test]
+fn serve_idle_connection_test() {
+    // A free port
+    let addr = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().to_string();
+    let _server = Server(
+        Command::new(env!("CARGO_BIN_EXE_dhcpd-leases"))
+            .args(["-f", "tests/data/dhcpd-multiple.leases", "serve", "--listen", &addr])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .unwrap(),
+    );
+
+    let mut idle = None;
+    for _ in 0..100 {
+        match TcpStream::connect(&addr) {
+            Ok(stream) => {
+                idle = Some(stream);
+                break;
+            }
+            Err(_) => thread::sleep(Duration::from_millis(50)),
+        }
+    }
+    // The idle connection sends nothing, and does not block the scrapes
+    assert!(idle.is_some(), "The server did not start");
+
+    let response = scrape(&addr, "/metrics");
+    let not_found = scrape(&addr, "/other");
+
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{}", response);
+    assert!(response.ends_with("# EOF\n"));
+    assert!(not_found.starts_with("HTTP/1.1 404 Not Found\r\n"));
+}
@@ -0,0 +1,119 @@
+extern crate dhcpd_parser;
+
+use std::net::Ipv4Addr;
+
+use chrono::prelude::*;
+
+use crate::dhcpd_parser::client::{self, networkd, DhcpOption};
+use crate::dhcpd_parser::common::Date;
+
+#[test]
+fn client_leases_file_test() {
+    let leases = client::parse_file("tests/data/dhclient.leases").unwrap();
+    assert_eq!(leases.len(), 3);
+
+    let lease = &leases[0];
+    assert_eq!(lease.interface.as_deref(), Some("eth0"));
+    assert_eq!(lease.ip(), Some(Ipv4Addr::new(192, 168, 0, 2)));
+    assert_eq!(lease.subnet_mask(), Some(Ipv4Addr::new(255, 255, 255, 0)));
+    assert_eq!(lease.routers(), [Ipv4Addr::new(192, 168, 0, 1)]);
+    assert_eq!(lease.domain_name_servers(), [Ipv4Addr::new(192, 168, 0, 1), Ipv4Addr::new(9, 9, 9, 9)]);
+    assert_eq!(lease.server_identifier(), Some(Ipv4Addr::new(192, 168, 0, 1)));
+    assert_eq!(lease.lease_time(), Some(3600));
+    assert!(lease.options.contains(&DhcpOption::MessageType(5)));
+    assert!(lease.options.contains(&DhcpOption::DomainName("home; lan".to_owned())));
+    assert!(lease
+        .options
+        .contains(&DhcpOption::DomainSearch(vec!["home.lan".to_owned(), "example.org".to_owned()])));
+    assert!(lease.options.contains(&DhcpOption::Other {
+        name: "vendor-encapsulated-options".to_owned(),
+        value: "1:4:c0:a8:0:1".to_owned(),
+    }));
+    assert_eq!(lease.renew.unwrap().to_rfc3339(), "2019-01-01T21:30:00Z");
+    assert_eq!(lease.rebind.unwrap().to_rfc3339(), "2019-01-01T21:52:30Z");
+    assert_eq!(lease.expire.unwrap().to_rfc3339(), "2019-01-01T22:00:00Z");
+
+    let at = Utc.with_ymd_and_hms(2019, 1, 1, 21, 0, 0).unwrap();
+    assert!(lease.is_valid_at(at));
+    assert!(!lease.is_valid_at(at + chrono::Duration::hours(2)));
+
+    // Epoch and never
+    assert_eq!(leases[1].renew, leases[0].renew);
+    assert_eq!(leases[1].rebind, Some(Date::NEVER));
+    assert!(leases[1].is_valid_at(at));
+
+    assert!(leases[2].bootp);
+    assert_eq!(leases[2].server_name.as_deref(), Some("boot"));
+    assert_eq!(leases[2].filename.as_deref(), Some("pxelinux.0"));
+
+    let latest: Vec<&str> = client::latest(&leases)
+        .iter()
+        .map(|l| l.fixed_address.as_deref().unwrap())
+        .collect();
+    assert_eq!(latest, ["10.0.0.5", "192.168.0.3"]);
+}
+
+#[test]
+fn client_leases_errors_test() {
+    assert_eq!(
+        client::parse("lease { option routers 192.168.0.x; }").err(),
+        Some("lease 1: '192.168.0.x' is not a valid IPv4 address for option routers".to_owned()),
+    );
+    assert_eq!(
+        client::parse("lease { expire soon; }").err(),
+        Some("lease 1: Invalid expire time 'soon'".to_owned()),
+    );
+    assert!(client::parse("lease { interface \"eth0\";").is_err());
+}
+
+#[test]
+fn client_leases_non_utf8_test() {
+    let dir = std::env::temp_dir().join(format!("dhcpd-parser-client-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("dhclient.leases");
+    std::fs::write(&path, b"lease {\n  interface \"eth0\";\n  option host-name \"caf\xe9\";\n}\n").unwrap();
+
+    let leases = client::parse_file(&path).unwrap();
+    assert_eq!(leases.len(), 1);
+    assert_eq!(leases[0].interface.as_deref(), Some("eth0"));
+    assert_eq!(leases[0].options.len(), 1);
+}
+
+#[test]
+fn networkd_lease_test() {
+    let contents = std::fs::read_to_string("tests/data/networkd/999999").unwrap();
+    let acquired = Utc.with_ymd_and_hms(2019, 1, 1, 21, 0, 0).unwrap();
+    let lease = networkd::parse(&contents, Some(acquired)).unwrap();
+
+    assert_eq!(lease.ip(), Some(Ipv4Addr::new(192, 168, 0, 2)));
+    assert_eq!(lease.subnet_mask(), Some(Ipv4Addr::new(255, 255, 255, 0)));
+    assert_eq!(lease.routers(), [Ipv4Addr::new(192, 168, 0, 1)]);
+    assert_eq!(lease.domain_name_servers(), [Ipv4Addr::new(192, 168, 0, 1), Ipv4Addr::new(9, 9, 9, 9)]);
+    assert_eq!(lease.server_identifier(), Some(Ipv4Addr::new(192, 168, 0, 1)));
+    assert!(lease.options.contains(&DhcpOption::InterfaceMtu(1500)));
+    assert!(lease.options.contains(&DhcpOption::Other {
+        name: "CLIENTID".to_owned(),
+        value: "ff0ad2fe8a00020000ab11".to_owned(),
+    }));
+    assert_eq!(lease.renew.unwrap().to_rfc3339(), "2019-01-01T21:30:00Z");
+    assert_eq!(lease.rebind.unwrap().to_rfc3339(), "2019-01-01T21:52:30Z");
+    assert_eq!(lease.expire.unwrap().to_rfc3339(), "2019-01-01T22:00:00Z");
+
+    // Same model as the dhclient lease
+    let dhclient = &client::parse_file("tests/data/dhclient.leases").unwrap()[0];
+    assert_eq!((lease.renew, lease.rebind, lease.expire), (dhclient.renew, dhclient.rebind, dhclient.expire));
+
+    // No such interface index on the host
+    let lease = networkd::parse_file("tests/data/networkd/999999").unwrap();
+    assert_eq!(lease.interface.as_deref(), Some("999999"));
+    assert!(lease.expire.is_some());
+
+    assert_eq!(
+        networkd::parse("ADDRESS=192.168.0.2\nLIFETIME\n", None).err(),
+        Some("line 2: Expected KEY=VALUE, found 'LIFETIME'".to_owned()),
+    );
+    assert_eq!(
+        networkd::parse("ROUTER=192.168.0.1 gateway\n", None).err(),
+        Some("line 1: 'gateway' is not a valid IPv4 address for ROUTER".to_owned()),
+    );
+}
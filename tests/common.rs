@@ -1,5 +1,7 @@
 extern crate dhcpd_parser;
 
+use std::cmp::Ordering;
+
 use crate::dhcpd_parser::common::Date;
 
 #[test]
@@ -39,3 +41,92 @@ fn date_rfc3339() {
         "This doesn\'t seem like a correct RFC3339 date: \"T\"",
     );
 }
+
+#[test]
+fn date_invalid() {
+    assert_eq!(
+        Date::from("2", "2019/01/01", "xx:00:00").unwrap_err(),
+        "Hour should be a number. xx is not",
+    );
+    assert_eq!(
+        Date::from("2", "2019/01/01", "22:60:00").unwrap_err(),
+        "Minute should be a number between 0 and 59. 60 is not",
+    );
+    assert_eq!(
+        Date::from("2", "2019/01/01", "22:00:61").unwrap_err(),
+        "Second should be a number between 0 and 59. 61 is not",
+    );
+    assert!(Date::from("x", "2019/01/01", "22:00:00").is_err());
+    assert!(Date::from("2", "2019/13/01", "22:00:00").is_err());
+    assert!(Date::from("2", "2019/01/aa", "22:00:00").is_err());
+
+    // The day is checked against the month
+    assert_eq!(
+        Date::from("2", "2021/02/30", "22:00:00").unwrap_err(),
+        "2021/02/30 22:00:00 is not a valid date",
+    );
+    assert!(Date::from("5", "2021/04/31", "22:00:00").is_err());
+    assert!(Date::from("1", "2021/02/29", "22:00:00").is_err());
+    assert!(Date::from("2", "2020/02/29", "22:00:00").is_ok());
+
+    // A date built field by field does not panic
+    let mut date = Date::new();
+    date.month = 2;
+    date.day = 30;
+    assert_eq!(date.to_chrono().timestamp(), 0);
+}
+
+#[test]
+fn date_ord() {
+    let date = Date::from("2", "2019/01/01", "22:00:00").unwrap();
+    let later = Date::from("2", "2019/01/01", "22:00:01").unwrap();
+
+    assert_eq!(date.cmp(&date), Ordering::Equal);
+    assert_eq!(date.partial_cmp(&date), Some(Ordering::Equal));
+    assert!(date < later);
+    assert!(later >= date);
+    assert_eq!(std::cmp::max(date, date), date);
+
+    let mut dates = vec![later, date, later, date];
+    dates.sort();
+    assert_eq!(dates, vec![date, date, later, later]);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn date_serde() {
+    let date = Date::from("2", "2019/01/01", "22:00:00").unwrap();
+    let json = serde_json::to_string(&date).unwrap();
+    assert_eq!(json, "\"2019-01-01T22:00:00Z\"");
+    assert_eq!(serde_json::from_str::<Date>(&json).unwrap(), date);
+
+    // Converted to UTC
+    assert_eq!(
+        serde_json::from_str::<Date>("\"2019-01-01T23:30:00+01:30\"").unwrap(),
+        date,
+    );
+    assert!(serde_json::from_str::<Date>("\"2019/01/01 22:00:00\"").is_err());
+}
+
+#[test]
+fn ip_range() {
+    use std::net::Ipv4Addr;
+    use crate::dhcpd_parser::common::IpRange;
+
+    let subnet = IpRange::from("192.168.4.17/24").unwrap();
+    assert_eq!(subnet.first, Ipv4Addr::new(192, 168, 4, 0));
+    assert_eq!(subnet.last, Ipv4Addr::new(192, 168, 4, 255));
+    assert_eq!(subnet.size(), 256);
+    assert_eq!(subnet.to_string(), "192.168.4.0/24");
+
+    let range = IpRange::from("192.168.4.100-192.168.4.200").unwrap();
+    assert!(range.contains(Ipv4Addr::new(192, 168, 4, 100)));
+    assert!(range.contains(Ipv4Addr::new(192, 168, 4, 200)));
+    assert!(!range.contains(Ipv4Addr::new(192, 168, 4, 201)));
+    assert_eq!(range.to_string(), "192.168.4.100-192.168.4.200");
+
+    assert_eq!(IpRange::from("0.0.0.0/0").unwrap().size(), 1 << 32);
+    assert!(IpRange::from("192.168.4.0/33").is_err());
+    assert!(IpRange::from("192.168.4.200-192.168.4.100").is_err());
+    assert!(IpRange::from("192.168.4.0").is_err());
+}
@@ -0,0 +1,130 @@
+extern crate dhcpd_parser;
+
+use std::net::Ipv4Addr;
+use std::path::PathBuf;
+
+use crate::dhcpd_parser::config::{self, Declaration, Statement};
+
+#[test]
+fn config_file_test() {
+    let config = config::parse_file("tests/data/conf/dhcpd.conf").unwrap();
+    assert_eq!(config.source, Some(PathBuf::from("tests/data/conf/dhcpd.conf")));
+
+    assert_eq!(config.option("domain-name").unwrap().values(), ["example.org"]);
+    assert_eq!(
+        config.option("domain-name-servers").unwrap().values(),
+        ["ns1.example.org", "ns2.example.org"],
+    );
+    assert_eq!(config.parameter("max-lease-time").unwrap().values, ["7200"]);
+    assert!(config.parameter("authoritative").is_some());
+
+    let peer = config.failover_peers()[0];
+    assert_eq!(peer.name, "dhcp-failover");
+    assert_eq!(peer.role(), Some("primary"));
+    assert_eq!(peer.mclt(), Some(3600));
+    assert_eq!(peer.split(), Some(128));
+
+    let subnets = config.subnets();
+    assert_eq!(subnets.len(), 2);
+    assert_eq!(subnets[0].network, Ipv4Addr::new(10, 0, 1, 0));
+    assert_eq!(subnets[0].option("routers").unwrap().value, "10.0.1.1");
+    assert_eq!(subnets[1].prefix(), 23);
+    assert_eq!(subnets[1].ip_range().to_string(), "10.0.2.0/23");
+
+    let ranges = subnets[0].ranges();
+    assert_eq!(ranges.len(), 2);
+    assert_eq!(ranges[0].range.to_string(), "10.0.1.100-10.0.1.199");
+    assert!(!ranges[0].dynamic_bootp);
+    assert!(ranges[1].dynamic_bootp);
+    assert_eq!(subnets[1].ranges()[0].range.size(), 1);
+
+    // The host of the included file is found along with the one in the group
+    let hosts = config.hosts();
+    assert_eq!(hosts.len(), 2);
+    assert_eq!(hosts[0].name, "printer");
+    assert_eq!(hosts[0].hardware.as_ref().unwrap().mac, "08:00:2b:4c:59:23");
+    assert_eq!(hosts[0].fixed_addresses, ["10.0.1.5", "printer.example.org"]);
+    assert!(hosts[0].statements.is_empty());
+    assert_eq!(hosts[1].name, "laptop");
+
+    match config.statements.last().unwrap() {
+        Statement::Block(block) => {
+            assert_eq!(block.words, ["on", "commit"]);
+            assert_eq!(block.parameter("set").unwrap().values[0], "clip");
+        }
+        s => panic!("Unexpected statement {:?}", s),
+    }
+}
+
+#[test]
+fn config_declarations_test() {
+    let config = config::parse(
+        "class \"voip\" {
+            match if substring (option vendor-class-identifier, 0, 4) = \"VoIP\";
+        }
+        subclass \"voip\" 1:00:0b:82:aa:bb:cc;
+        subclass \"voip\" \"SIP phone\" { option tftp-server-name \"10.0.0.5\"; }
+        shared-network \"office\" {
+            option domain-name \"office; example.org\";
+        }
+        include \"hosts.conf\";",
+    )
+    .unwrap();
+
+    let statements = &config.statements;
+    assert_eq!(statements.len(), 5);
+
+    match &statements[0] {
+        Statement::Class(class) => {
+            assert_eq!(class.name, "voip");
+            assert_eq!(class.parameter("match").unwrap().values[0], "if");
+        }
+        s => panic!("Unexpected statement {:?}", s),
+    }
+
+    match (&statements[1], &statements[2]) {
+        (Statement::Subclass(first), Statement::Subclass(second)) => {
+            assert_eq!(first.class, "voip");
+            assert_eq!(first.value, "1:00:0b:82:aa:bb:cc");
+            assert_eq!(second.value, "SIP phone");
+            assert_eq!(second.option("tftp-server-name").unwrap().value, "\"10.0.0.5\"");
+        }
+        s => panic!("Unexpected statements {:?}", s),
+    }
+
+    // The quoted string is a single value, even with a ';' in it
+    match &statements[3] {
+        Statement::SharedNetwork(network) => {
+            assert_eq!(network.name, "office");
+            assert_eq!(network.option("domain-name").unwrap().values(), ["office; example.org"]);
+        }
+        s => panic!("Unexpected statement {:?}", s),
+    }
+
+    // Only parse_file loads the included files
+    match &statements[4] {
+        Statement::Include(include) => {
+            assert_eq!(include.path, PathBuf::from("hosts.conf"));
+            assert!(include.statements.is_empty());
+        }
+        s => panic!("Unexpected statement {:?}", s),
+    }
+}
+
+#[test]
+fn config_errors_test() {
+    assert_eq!(
+        config::parse("default-lease-time 600;\nsubnet 10.0.0.0 netmask 255.0.0.0 {\n range 10.0.0.1 10.0.0.x;\n}"),
+        Err("line 3: '10.0.0.x' is not a valid IPv4 address".to_owned()),
+    );
+    assert_eq!(
+        config::parse("pool {\n range 10.0.0.1 10.0.0.9;\n"),
+        Err("line 3: Expected '}', found end of input".to_owned()),
+    );
+    assert_eq!(
+        config::parse("authoritative }"),
+        Err("line 1: Expected ';' after 'authoritative'".to_owned()),
+    );
+    assert_eq!(config::parse("}"), Err("line 1: Unexpected '}'".to_owned()));
+    assert!(config::parse_file("tests/data/conf/missing.conf").is_err());
+}
@@ -0,0 +1,88 @@
+extern crate dhcpd_parser;
+
+use std::net::Ipv4Addr;
+
+use chrono::prelude::*;
+
+use crate::dhcpd_parser::common::IpRange;
+use crate::dhcpd_parser::config;
+use crate::dhcpd_parser::correlate::Correlator;
+use crate::dhcpd_parser::parser;
+
+const LEASES: &str = "
+lease 10.0.1.100 {
+  starts 1 2019/01/01 20:00:00;
+  ends 1 2019/01/01 22:00:00;
+  binding state active;
+}
+lease 10.0.1.101 {
+  starts 1 2019/01/01 18:00:00;
+  ends 1 2019/01/01 19:00:00;
+  binding state free;
+}
+lease 10.0.1.102 {
+  starts 1 2019/01/01 18:00:00;
+  ends 1 2019/01/01 19:00:00;
+  abandoned;
+}
+lease 10.0.1.210 {
+  starts 1 2019/01/01 20:00:00;
+  ends 1 2019/01/01 22:00:00;
+  binding state active;
+}
+lease 10.0.1.5 {
+  starts 1 2019/01/01 20:00:00;
+  ends 1 2019/01/01 22:00:00;
+  binding state active;
+}
+lease 10.0.3.7 {
+  starts 1 2019/01/01 20:00:00;
+  ends 1 2019/01/01 22:00:00;
+  binding state active;
+}
+";
+
+fn at() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2019, 1, 1, 21, 0, 0).unwrap()
+}
+
+#[test]
+fn correlate_config_test() {
+    let config = config::parse_file("tests/data/conf/dhcpd.conf").unwrap();
+    let leases = parser::parse(LEASES).unwrap().leases;
+    let report = Correlator::from_config(&config).at(at()).report(&leases);
+
+    let names: Vec<&str> = report.pools.iter().map(|p| p.name.as_str()).collect();
+    assert_eq!(names, ["10.0.1.0/24 pool 1", "10.0.1.0/24 pool 2", "10.0.2.0/23"]);
+
+    let pool = &report.pools[0];
+    assert_eq!((pool.size, pool.active, pool.abandoned, pool.free), (100, 1, 1, 98));
+    assert!((pool.utilisation() - 0.01).abs() < 1e-9);
+    assert_eq!((report.pools[1].size, report.pools[1].active), (51, 1));
+    assert_eq!(report.pools[2].size, 1);
+
+    // The lease of the fixed address of the printer is not out of range
+    let out: Vec<&str> = report.out_of_range.iter().map(|l| l.ip.as_str()).collect();
+    assert_eq!(out, ["10.0.3.7"]);
+    assert!(report.collisions.is_empty());
+}
+
+#[test]
+fn correlate_collisions_test() {
+    let leases = parser::parse(LEASES).unwrap().leases;
+    let report = Correlator::new()
+        .pool("dynamic", &[IpRange::from("10.0.1.0/24").unwrap()])
+        .host("printer", Ipv4Addr::new(10, 0, 1, 5))
+        .host("laptop", Ipv4Addr::new(10, 0, 1, 6))
+        .host("server", Ipv4Addr::new(10, 0, 9, 1))
+        .at(at())
+        .report(&leases);
+
+    assert_eq!(report.pools[0].active, 3);
+    assert_eq!(report.collisions.len(), 2);
+    assert_eq!(report.collisions[0].host, "printer");
+    assert_eq!(report.collisions[0].pool, "dynamic");
+    assert_eq!(report.collisions[0].lease.as_ref().unwrap().ip, "10.0.1.5");
+    assert_eq!(report.collisions[1].host, "laptop");
+    assert!(report.collisions[1].lease.is_none());
+}
@@ -0,0 +1,130 @@
+extern crate dhcpd_parser;
+
+use std::net::IpAddr;
+
+use crate::dhcpd_parser::dns::{reverse_name, sanitize_label, DnsBuilder, Duplicates, Sanitize};
+use crate::dhcpd_parser::leases::Leases;
+use crate::dhcpd_parser::parser;
+
+const LEASES: &str = "
+lease 192.168.0.2 {
+  starts 1 2019/01/01 20:00:00;
+  client-hostname \"laptop.home\";
+}
+lease 192.168.0.3 {
+  starts 1 2019/01/01 21:00:00;
+  client-hostname \"LAPTOP\";
+}
+lease 192.168.0.4 {
+  starts 1 2019/01/01 19:00:00;
+  hostname \"printer\";
+  client-hostname \"laptop\";
+}
+lease 192.168.0.5 {
+  starts 1 2019/01/01 19:00:00;
+  client-hostname \"laptop-2\";
+}
+lease 192.168.0.6 {
+  starts 1 2019/01/01 19:00:00;
+  client-hostname \"_\";
+}
+lease 192.168.0.7 {
+  starts 1 2019/01/01 19:00:00;
+}
+";
+
+fn names(leases: &Leases, builder: &DnsBuilder) -> Vec<(String, String)> {
+    builder
+        .records(leases)
+        .into_iter()
+        .map(|r| (r.name, r.ip.to_string()))
+        .collect()
+}
+
+fn pairs(expected: &[(&str, &str)]) -> Vec<(String, String)> {
+    expected.iter().map(|(n, ip)| (n.to_string(), ip.to_string())).collect()
+}
+
+#[test]
+fn dns_duplicates_test() {
+    let leases = parser::parse(LEASES).unwrap().leases;
+    let mut builder = DnsBuilder::new("example.com");
+
+    assert_eq!(
+        names(&leases, builder.duplicates(Duplicates::Latest)),
+        pairs(&[("laptop", "192.168.0.3"), ("printer", "192.168.0.4"), ("laptop-2", "192.168.0.5")]),
+    );
+    assert_eq!(
+        names(&leases, builder.duplicates(Duplicates::First)),
+        pairs(&[("laptop", "192.168.0.2"), ("printer", "192.168.0.4"), ("laptop-2", "192.168.0.5")]),
+    );
+    assert_eq!(
+        names(&leases, builder.duplicates(Duplicates::Drop)),
+        pairs(&[("printer", "192.168.0.4"), ("laptop-2", "192.168.0.5")]),
+    );
+    assert_eq!(
+        names(&leases, builder.duplicates(Duplicates::All)),
+        pairs(&[
+            ("laptop", "192.168.0.2"),
+            ("laptop", "192.168.0.3"),
+            ("printer", "192.168.0.4"),
+            ("laptop-2", "192.168.0.5"),
+        ]),
+    );
+    // "laptop-2" is taken by another lease
+    assert_eq!(
+        names(&leases, builder.duplicates(Duplicates::Suffix)),
+        pairs(&[
+            ("laptop", "192.168.0.2"),
+            ("laptop-3", "192.168.0.3"),
+            ("printer", "192.168.0.4"),
+            ("laptop-2", "192.168.0.5"),
+        ]),
+    );
+}
+
+#[test]
+fn dns_sanitize_test() {
+    assert_eq!(sanitize_label("Bob's iPhone", Sanitize::Replace), Some("bob-s-iphone".to_owned()));
+    assert_eq!(sanitize_label("-host-.lan", Sanitize::Replace), Some("host".to_owned()));
+    assert_eq!(sanitize_label("___", Sanitize::Replace), None);
+    assert_eq!(sanitize_label(&"a".repeat(70), Sanitize::Replace), Some("a".repeat(63)));
+
+    assert_eq!(sanitize_label("Host-1.lan", Sanitize::Skip), Some("host-1".to_owned()));
+    assert_eq!(sanitize_label("Bob's iPhone", Sanitize::Skip), None);
+    assert_eq!(sanitize_label(&"a".repeat(70), Sanitize::Skip), None);
+}
+
+#[test]
+fn dns_write_test() {
+    let leases = parser::parse(LEASES).unwrap().leases;
+    let mut builder = DnsBuilder::new("example.com.");
+    builder.ttl(300).duplicates(Duplicates::First);
+
+    let mut zone = Vec::new();
+    builder.write_zone(&leases, &mut zone).unwrap();
+    assert_eq!(
+        String::from_utf8(zone).unwrap(),
+        "$ORIGIN example.com.\n$TTL 300\n\
+         laptop\tIN\tA\t192.168.0.2\n\
+         printer\tIN\tA\t192.168.0.4\n\
+         laptop-2\tIN\tA\t192.168.0.5\n",
+    );
+
+    let mut reverse = Vec::new();
+    builder.write_reverse_zone(&leases, &mut reverse).unwrap();
+    assert!(String::from_utf8(reverse)
+        .unwrap()
+        .contains("2.0.168.192.in-addr.arpa.\tIN\tPTR\tlaptop.example.com.\n"));
+
+    let mut hosts = Vec::new();
+    builder.write_hosts(&leases, &mut hosts).unwrap();
+    assert!(String::from_utf8(hosts)
+        .unwrap()
+        .starts_with("192.168.0.2\tlaptop.example.com laptop\n"));
+
+    assert_eq!(
+        reverse_name("2001:db8::1".parse::<IpAddr>().unwrap()),
+        "1.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.8.b.d.0.1.0.0.2.ip6.arpa.",
+    );
+}
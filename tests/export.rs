@@ -0,0 +1,127 @@
+extern crate dhcpd_parser;
+
+use dhcpd_parser::export::{self, write_leases, Column, DEFAULT_COLUMNS};
+use dhcpd_parser::leases::LeasesMethods;
+use dhcpd_parser::parser;
+
+#[test]
+fn csv_test() {
+    let leases = parser::parse_file("tests/data/dhcpd-linux.leases").unwrap().leases;
+
+    let mut out = Vec::new();
+    export::write_csv(&leases, &DEFAULT_COLUMNS, &mut out).unwrap();
+    let csv = String::from_utf8(out).unwrap();
+    let lines: Vec<&str> = csv.lines().collect();
+
+    assert_eq!(lines.len(), 7);
+    assert_eq!(lines[0], "ip,mac,hostname,client-hostname,uid,binding-state,starts,ends,cltt");
+    assert_eq!(
+        lines[1],
+        "192.168.4.101,00:ab:d4:41:21:10,,,\"\"\"\\001\\000\\352\\324A!\\020\"\"\",free,\
+         2021-11-30T19:57:47Z,2021-11-30T21:57:47Z,2021-11-30T19:57:47Z",
+    );
+}
+
+#[test]
+fn column_from_test() {
+    assert_eq!(Column::from("client-hostname"), Ok(Column::ClientHostname));
+    assert!(Column::from("vendor").is_err());
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn ndjson_test() {
+    let leases = parser::parse_file("tests/data/dhcpd-linux.leases").unwrap().leases;
+
+    let mut out = Vec::new();
+    export::write_ndjson(&leases, &mut out).unwrap();
+    let ndjson = String::from_utf8(out).unwrap();
+
+    assert_eq!(ndjson.lines().count(), 6);
+    for line in ndjson.lines() {
+        let value: serde_json::Value = serde_json::from_str(line).unwrap();
+        assert!(value["ip"].as_str().unwrap().starts_with("192.168.4."));
+    }
+
+    let mut out = Vec::new();
+    export::write_json(&leases, &mut out).unwrap();
+    let value: serde_json::Value = serde_json::from_slice(&out).unwrap();
+    assert_eq!(value.as_array().unwrap().len(), 6);
+}
+
+#[test]
+fn write_leases_test() {
+    use dhcpd_parser::parser::{Dialect, ParseOptions};
+
+    // The written files follow the grammar of their dialect
+    for (path, dialect) in [
+        ("tests/data/dhcpd-linux.leases", Dialect::Isc),
+        ("tests/data/dhcpd-multiple.leases", Dialect::Isc),
+        ("tests/data/dhcpd6.leases", Dialect::IscV6),
+    ] {
+        let options = ParseOptions { dialect, strict: true };
+        let leases = parser::parse_with(&std::fs::read_to_string(path).unwrap(), &options).unwrap().leases;
+
+        let mut out = Vec::new();
+        write_leases(&leases, dialect, &mut out).unwrap();
+        let written = String::from_utf8(out).unwrap();
+
+        assert_eq!(parser::parse_with(&written, &options).unwrap().leases, leases, "{}", path);
+        assert_eq!(parser::detect_format(&written), dialect);
+    }
+
+    let leases = parser::parse_file("tests/data/dhcpd-linux.leases").unwrap().leases;
+    let mut out = Vec::new();
+    write_leases(&leases, Dialect::OpenBsd, &mut out).unwrap();
+    let written = String::from_utf8(out).unwrap();
+
+    let options = ParseOptions { dialect: Dialect::OpenBsd, strict: true };
+    let bsd = parser::parse_with(&written, &options).unwrap().leases;
+    assert_eq!(bsd.count(), leases.count());
+    assert_eq!(bsd[0].dates.starts, leases[0].dates.starts);
+    assert_eq!(bsd[0].dates.tstp, None);
+    assert_eq!(bsd[0].binding_state, None);
+    assert!(written.starts_with("lease 192.168.4.101 {\n  starts 2 2021/11/30 19:57:47 UTC;\n  ends 2 2021/11/30 21:57:47 UTC;\n  hardware ethernet 00:ab:d4:41:21:10;\n"));
+}
+
+#[test]
+fn write_leases_never_test() {
+    use dhcpd_parser::common::Date;
+    use dhcpd_parser::parser::{Dialect, ParseOptions};
+
+    let input = "lease 192.168.0.2 {\n  starts 2 2019/01/01 22:00:00;\n  ends never;\n  binding state active;\n}\n";
+    let leases = parser::parse(input).unwrap().leases;
+    assert_eq!(leases[0].dates.ends, Some(Date::NEVER));
+
+    let mut out = Vec::new();
+    write_leases(&leases, Dialect::Isc, &mut out).unwrap();
+    let written = String::from_utf8(out).unwrap();
+    assert!(written.contains("  ends never;\n"));
+    assert_eq!(parser::parse(written.as_str()).unwrap().leases, leases);
+
+    // OpenBSD has no "never"
+    let mut out = Vec::new();
+    write_leases(&leases, Dialect::OpenBsd, &mut out).unwrap();
+    let written = String::from_utf8(out).unwrap();
+    assert!(written.contains("  ends 5 9999/12/31 23:59:59 UTC;\n"));
+    let options = ParseOptions { dialect: Dialect::OpenBsd, strict: true };
+    assert_eq!(parser::parse_with(&written, &options).unwrap().leases[0].dates.ends, Some(Date::NEVER));
+}
+
+#[test]
+fn write_leases_ia_test() {
+    use dhcpd_parser::leases::{Lease, Leases};
+    use dhcpd_parser::parser::Dialect;
+
+    // A uid in hexadecimal is written as the quoted string of its bytes
+    let mut lease = Lease::new();
+    lease.ip = "2001:db8::1".to_owned();
+    lease.uid = Some("0e:00:fc:00:00:01:00:01:22:5c:41".to_owned());
+    let mut leases = Leases::new();
+    leases.push(lease);
+
+    let mut out = Vec::new();
+    write_leases(&leases, Dialect::IscV6, &mut out).unwrap();
+    let written = String::from_utf8(out).unwrap();
+    assert!(written.starts_with("ia-na \"\\016\\000\\374\\000\\000\\001\\000\\001\\042\\134A\" {\n"), "{}", written);
+}
@@ -0,0 +1,46 @@
+extern crate dhcpd_parser;
+
+use crate::dhcpd_parser::failover::{ConflictKind, FailoverCheck, Side};
+use crate::dhcpd_parser::parser;
+
+#[test]
+fn failover_check_test() {
+    let primary = parser::parse_file("tests/data/failover/primary.leases").unwrap().leases;
+    let secondary = parser::parse_file("tests/data/failover/secondary.leases").unwrap().leases;
+
+    let report = FailoverCheck::new().report(&primary, &secondary);
+
+    let conflicts: Vec<(&str, ConflictKind)> =
+        report.conflicts.iter().map(|c| (c.primary.ip.as_str(), c.kind)).collect();
+    assert_eq!(
+        conflicts,
+        vec![
+            ("192.168.4.102", ConflictKind::Owner),
+            ("192.168.4.105", ConflictKind::Client),
+            ("192.168.4.106", ConflictKind::BindingState),
+        ]
+    );
+    assert_eq!(report.conflicts[1].secondary.hardware.as_ref().unwrap().mac, "00:ab:d4:41:21:25");
+
+    assert!(report.only_primary.is_empty());
+    assert_eq!(report.only_secondary.len(), 1);
+    assert_eq!(report.only_secondary[0].ip, "192.168.4.107");
+
+    assert_eq!(report.lagging.len(), 1);
+    assert_eq!(report.lagging[0].side, Side::Primary);
+    assert_eq!(report.lagging[0].lease.ip, "192.168.4.103");
+    assert_eq!(report.lagging[0].seconds, 4 * 3600);
+
+    assert_eq!(report.missing_atsfp.len(), 1);
+    assert_eq!(report.missing_atsfp[0].0, Side::Primary);
+    assert_eq!(report.missing_atsfp[0].1.ip, "192.168.4.104");
+
+    // A longer MCLT tolerates the lag
+    let report = FailoverCheck::new().mclt(5 * 3600).report(&primary, &secondary);
+    assert!(report.lagging.is_empty());
+
+    // The partners agree with themselves
+    let report = FailoverCheck::new().report(&secondary, &secondary);
+    assert!(report.conflicts.is_empty());
+    assert!(report.only_primary.is_empty() && report.only_secondary.is_empty());
+}
@@ -0,0 +1,228 @@
+extern crate dhcpd_parser;
+
+use std::path::PathBuf;
+
+use chrono::prelude::*;
+
+use crate::dhcpd_parser::common::{Date, IpRange};
+use crate::dhcpd_parser::formats::kea::KeaWriter;
+use crate::dhcpd_parser::formats::{dnsmasq, kea};
+use crate::dhcpd_parser::leases::LeasesMethods;
+use crate::dhcpd_parser::parser;
+use crate::dhcpd_parser::util::LeaseFilterBuilder;
+
+#[test]
+fn dnsmasq_file_test() {
+    let res = dnsmasq::parse_file("tests/data/dnsmasq.leases").unwrap();
+    assert_eq!(res.source, Some(PathBuf::from("tests/data/dnsmasq.leases")));
+
+    let leases = res.leases;
+    assert_eq!(leases.count(), 6);
+
+    assert_eq!(leases[0].ip, "192.168.0.2");
+    assert_eq!(leases[0].hardware.as_ref().unwrap().mac, "11:11:11:11:11:11");
+    assert_eq!(leases[0].hardware.as_ref().unwrap().h_type, "ethernet");
+    assert_eq!(leases[0].uid.as_deref(), Some("01:11:11:11:11:11:11"));
+    assert_eq!(leases[0].dates.ends.unwrap().to_rfc3339(), "2019-01-01T22:00:00Z");
+    assert_eq!(leases[0].dates.ends.unwrap().weekday, 2);
+
+    assert_eq!(leases[1].client_hostname, None);
+    assert_eq!(leases[1].uid, None);
+
+    // The lease that never ends, on a token ring
+    assert_eq!(leases[2].dates.ends, Some(Date::NEVER));
+    assert_eq!(leases[2].hardware.as_ref().unwrap().h_type, "token-ring");
+    assert_eq!(leases[2].hardware.as_ref().unwrap().mac, "aa:bb:cc:dd:ee:ff");
+    assert_eq!(leases[3].hardware.as_ref().unwrap().h_type, "fddi");
+
+    // The IAID comes before the DUID in the uid
+    assert_eq!(leases[4].ip, "2001:db8::10");
+    assert_eq!(leases[4].hardware, None);
+    assert_eq!(leases[4].uid.as_deref(), Some("00:12:d6:87:00:01:00:01:11:22:33:44:11:11:11:11:11:11"));
+    assert_eq!(leases[5].client_hostname, None);
+    assert_eq!(leases[5].uid.as_deref(), Some("00:74:cb:b1:00:01:00:01:11:22:33:44:22:22:22:22:22:22"));
+
+    // The filters work the same as for the dhcpd leases
+    let at = Utc.with_ymd_and_hms(2019, 1, 1, 22, 30, 0).unwrap();
+    let active = LeaseFilterBuilder::new(&leases).on_active_at(Some(at)).collect();
+    assert_eq!(active.all().iter().map(|l| l.ip.as_str()).collect::<Vec<_>>(), ["192.168.0.3", "192.168.0.4", "192.168.0.5"]);
+    assert_eq!(LeaseFilterBuilder::new(&leases).on_hostname("LAPTOP").collect().count(), 2);
+}
+
+#[test]
+fn dnsmasq_errors_test() {
+    assert_eq!(
+        dnsmasq::parse("1546380000 11:11:11:11:11:11 192.168.0.2 laptop\n").err(),
+        Some("line 1: Expected 5 fields, found 4".to_owned()),
+    );
+    assert_eq!(
+        dnsmasq::parse("\nsoon 11:11:11:11:11:11 192.168.0.2 laptop *\n").err(),
+        Some("line 2: 'soon' is not a valid expiry time".to_owned()),
+    );
+    assert_eq!(
+        dnsmasq::parse("1546380000 1234567 2001:db8::10 laptop *\n").err(),
+        Some("line 1: Expected an IPv4 address, found 2001:db8::10".to_owned()),
+    );
+    assert_eq!(
+        dnsmasq::parse("1546380000 x6-11:11:11:11:11:11 192.168.0.2 laptop *\n").err(),
+        Some("line 1: 'x6' is not a valid hardware type".to_owned()),
+    );
+    assert_eq!(
+        dnsmasq::parse("duid 00:01\n1546380000 11:11 2001:db8::10 laptop *\n").err(),
+        Some("line 2: '11:11' is not a valid IAID".to_owned()),
+    );
+}
+
+#[test]
+fn kea_v4_test() {
+    let res = kea::parse_file("tests/data/kea-leases4.csv").unwrap();
+    assert_eq!(res.source, Some(PathBuf::from("tests/data/kea-leases4.csv")));
+
+    let leases = res.leases;
+    assert_eq!(leases.count(), 3);
+    assert_eq!(leases[0].hardware.as_ref().unwrap().mac, "11:11:11:11:11:11");
+    assert_eq!(leases[0].uid.as_deref(), Some("01:11:11:11:11:11:11"));
+    assert_eq!(leases[0].hostname.as_deref(), Some("laptop.example.org"));
+    assert_eq!(leases[0].dates.ends.unwrap().to_rfc3339(), "2019-01-01T22:00:00Z");
+    assert_eq!(leases[0].dates.cltt.unwrap().to_rfc3339(), "2019-01-01T21:00:00Z");
+
+    // Declined
+    assert_eq!(leases[1].binding_state.as_deref(), Some("abandoned"));
+    assert!(leases[1].abandoned);
+    assert_eq!(leases[1].uid, None);
+
+    // The last line for the address is the effective lease
+    let effective = LeaseFilterBuilder::new(&leases).effective().collect();
+    assert_eq!(effective.count(), 2);
+    assert_eq!(effective[1].binding_state.as_deref(), Some("free"));
+
+    let records = kea::parse_records(&std::fs::read_to_string("tests/data/kea-leases4.csv").unwrap()).unwrap();
+    assert_eq!(records[0].subnet_id, 1);
+    assert_eq!(records[0].valid_lifetime, 3600);
+    assert!(records[0].fqdn_fwd && records[0].fqdn_rev);
+    assert_eq!(records[0].user_context.as_deref(), Some("{ \"comment\": \"a, b\" }"));
+    assert_eq!(records[0].pool_id, Some(0));
+    assert_eq!(records[0].iaid, None);
+}
+
+#[test]
+fn kea_v6_test() {
+    let records = kea::parse_records(&std::fs::read_to_string("tests/data/kea-leases6.csv").unwrap()).unwrap();
+    assert_eq!(records.len(), 2);
+
+    let lease = &records[0].lease;
+    assert_eq!(lease.ip, "2001:db8::10");
+    assert_eq!(lease.uid.as_deref(), Some("00:01:00:01:11:22:33:44:11:11:11:11:11:11"));
+    assert_eq!(lease.hardware.as_ref().unwrap().h_type, "ethernet");
+    assert_eq!(records[0].iaid, Some(1234567));
+    assert_eq!(records[0].prefix_len, Some(128));
+
+    // Delegated prefix, without hardware address
+    assert_eq!(records[1].lease_type, Some(2));
+    assert_eq!(records[1].prefix_len, Some(56));
+    assert_eq!(records[1].lease.hardware, None);
+    assert_eq!(records[1].pool_id, None);
+}
+
+#[test]
+fn kea_errors_test() {
+    assert_eq!(
+        kea::parse("address,hwaddr,valid_lifetime,expire,subnet_id\n").err(),
+        Some("line 1: Not a Kea memfile, the header has no 'client_id' column".to_owned()),
+    );
+    assert_eq!(
+        kea::parse("address,hwaddr,client_id,valid_lifetime,expire,subnet_id\n192.168.0.2,,,3600,1546380000\n").err(),
+        Some("line 2: Expected 6 values, found 5".to_owned()),
+    );
+    assert_eq!(
+        kea::parse("address,hwaddr,client_id,valid_lifetime,expire,subnet_id\n192.168.0.2,,,1h,1546380000,1\n").err(),
+        Some("line 2: '1h' is not a valid valid_lifetime".to_owned()),
+    );
+}
+
+#[test]
+fn kea_writer_test() {
+    let leases = parser::parse(
+        "lease 192.168.0.2 {
+          starts 2 2019/01/01 21:00:00;
+          ends 2 2019/01/01 22:00:00;
+          binding state free;
+          hardware ethernet 11:11:11:11:11:11;
+        }
+        lease 192.168.0.2 {
+          starts 2 2019/01/01 21:00:00;
+          ends 2 2019/01/01 22:00:00;
+          binding state active;
+          hardware ethernet 11:11:11:11:11:11;
+          uid \"\\001\\021\\021\\021\\021\\021\\021\";
+          client-hostname \"my,laptop\";
+        }
+        lease 192.168.0.3 {
+          starts 2 2019/01/01 21:00:00;
+          ends 2 2019/01/01 22:00:00;
+          binding state abandoned;
+        }
+        lease 192.168.0.4 {
+          starts 2 2019/01/01 21:00:00;
+          binding state active;
+          hardware ethernet 44:44:44:44:44:44;
+        }
+        lease 192.168.0.5 {
+          starts 2 2019/01/01 21:00:00;
+          ends 2 2019/01/01 22:00:00;
+          binding state active;
+        }
+        lease 192.168.0.6 {
+          starts 2 2019/01/01 21:00:00;
+          ends never;
+          binding state active;
+          hardware ethernet 66:66:66:66:66:66;
+        }
+        lease 10.0.0.2 {
+          starts 2 2019/01/01 21:00:00;
+          ends 2 2019/01/01 22:00:00;
+          hardware ethernet 22:22:22:22:22:22;
+        }",
+    )
+    .unwrap()
+    .leases;
+
+    let mut out = Vec::new();
+    let left_out = KeaWriter::new()
+        .subnet(IpRange::from("192.168.0.0/24").unwrap(), 1)
+        .write(&leases, &mut out)
+        .unwrap();
+    let out = String::from_utf8(out).unwrap();
+
+    assert_eq!(
+        out,
+        format!(
+            "{}\n{}\n{}\n{}\n",
+            kea::HEADER_V4,
+            "192.168.0.2,11:11:11:11:11:11,01:11:11:11:11:11:11,3600,1546380000,1,0,0,my&#x2claptop,0,",
+            "192.168.0.3,,,3600,1546380000,1,0,0,,1,",
+            "192.168.0.6,66:66:66:66:66:66,,4294967295,5841343695,1,0,0,,0,",
+        ),
+    );
+
+    let reasons: Vec<(&str, &str)> = left_out.iter().map(|u| (u.lease.ip.as_str(), u.reason.as_str())).collect();
+    assert_eq!(
+        reasons,
+        [
+            ("192.168.0.4", "There is no ends date"),
+            ("192.168.0.5", "There is no hardware address"),
+            ("10.0.0.2", "10.0.0.2 is in none of the subnets"),
+        ],
+    );
+
+    // Kea reads the leases back the same
+    let back = kea::parse(&out).unwrap().leases;
+    assert_eq!(back[0].hostname.as_deref(), Some("my,laptop"));
+    assert_eq!(back[0].dates.ends, leases[1].dates.ends);
+    assert_eq!(back[1].binding_state.as_deref(), Some("abandoned"));
+    assert_eq!(back[2].dates.ends, Some(Date::NEVER));
+    assert_eq!(back[2].dates.starts, leases[6].dates.starts);
+
+    assert_eq!(kea::client_id("\\001\\000\\021\"3DU"), "01:00:11:22:33:44:55");
+    assert_eq!(kea::client_id("01:AB:CD"), "01:ab:cd");
+}
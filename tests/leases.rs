@@ -328,3 +328,59 @@ fn linux_test() {
 
     assert_eq!(leases.count(), 2);
 }
+
+#[test]
+fn never_ends_test() {
+    use chrono::{TimeZone, Utc};
+    use dhcpd_parser::metrics::lease_state;
+    use dhcpd_parser::util::LeaseFilterBuilder;
+
+    let leases = parser::parse(
+        "lease 192.168.0.2 {
+        starts 2 2019/01/01 22:00:00;
+        ends 2 2019/01/01 23:00:00;
+        binding state active;
+    }
+    lease 192.168.0.2 {
+        starts 2 2019/01/01 22:30:00;
+        ends never;
+        binding state active;
+    }
+    lease 192.168.0.3 {
+        starts 2 2019/01/01 22:00:00;
+        binding state active;
+    }",
+    )
+    .unwrap()
+    .leases;
+
+    let lease = &leases[1];
+    let at = Utc.with_ymd_and_hms(2030, 1, 1, 0, 0, 0).unwrap();
+    assert!(lease.dates.ends.unwrap().is_never());
+    assert!(lease.is_bound_at(at));
+    assert!(lease.active_after(at));
+    assert_eq!(lease_state(lease, at), "active");
+    assert_eq!(lease.lease_end_dts(), Date::NEVER.to_chrono());
+
+    // Without an "ends" date
+    assert_eq!(leases[2].lease_end_dts().timestamp(), 0);
+
+    let latest = LeaseFilterBuilder::new(&leases).latest().collect();
+    assert_eq!(latest.all(), vec![lease.clone()]);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serde_round_trip_test() {
+    let res = parser::parse_file("tests/data/dhcpd-linux.leases").unwrap();
+
+    let json = serde_json::to_value(&res).unwrap();
+    assert_eq!(json["source"], "tests/data/dhcpd-linux.leases");
+    assert_eq!(json["leases"][0]["ip"], "192.168.4.101");
+    assert_eq!(json["leases"][0]["dates"]["starts"], "2021-11-30T19:57:47Z");
+    assert_eq!(json["leases"][0]["hardware"]["mac"], "00:ab:d4:41:21:10");
+    assert_eq!(json["leases"][0]["binding_state"], "free");
+
+    let back: parser::ParserResult = serde_json::from_value(json).unwrap();
+    assert_eq!(back, res);
+}
@@ -0,0 +1,97 @@
+extern crate dhcpd_parser;
+
+use std::time::{Duration, SystemTime};
+
+use chrono::prelude::*;
+
+use crate::dhcpd_parser::common::IpRange;
+use crate::dhcpd_parser::metrics::MetricsBuilder;
+use crate::dhcpd_parser::parser;
+
+const LEASES: &str = "
+lease 192.168.0.2 {
+  starts 1 2019/01/01 20:00:00;
+  ends 1 2019/01/01 22:00:00;
+  binding state active;
+  hardware ethernet 11:11:11:11:11:11;
+}
+lease 192.168.0.3 {
+  starts 1 2019/01/01 20:00:00;
+  ends 1 2019/01/01 20:30:00;
+  binding state free;
+  hardware ethernet 22:22:22:22:22:22;
+}
+lease 192.168.0.3 {
+  starts 1 2019/01/01 20:00:00;
+  ends 1 2019/01/01 21:30:00;
+  binding state active;
+  hardware ethernet 22:22:22:22:22:22;
+}
+lease 192.168.0.4 {
+  starts 1 2019/01/01 18:00:00;
+  ends 1 2019/01/01 19:00:00;
+  binding state active;
+  hardware ethernet 33:33:33:33:33:33;
+}
+lease 192.168.1.5 {
+  starts 1 2019/01/01 18:00:00;
+  ends 1 2019/01/01 19:00:00;
+  abandoned;
+}
+";
+
+fn at() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2019, 1, 1, 21, 0, 0).unwrap()
+}
+
+#[test]
+fn metrics_by_subnet_test() {
+    let leases = parser::parse(LEASES).unwrap().leases;
+    let modified = SystemTime::from(at()) - Duration::from_secs(90);
+    let text = MetricsBuilder::new()
+        .at(at())
+        .buckets(&[1800, 600])
+        .parse_errors(2)
+        .file_modified(modified)
+        .render(&leases);
+
+    assert!(text.contains("dhcpd_leases{pool=\"192.168.0.0/24\",state=\"active\"} 2\n"));
+    assert!(text.contains("dhcpd_leases{pool=\"192.168.0.0/24\",state=\"expired\"} 1\n"));
+    assert!(text.contains("dhcpd_leases{pool=\"192.168.0.0/24\",state=\"free\"} 0\n"));
+    assert!(text.contains("dhcpd_leases{pool=\"192.168.1.0/24\",state=\"abandoned\"} 1\n"));
+
+    assert!(text.contains("dhcpd_lease_expiry_seconds_bucket{pool=\"192.168.0.0/24\",le=\"600\"} 0\n"));
+    assert!(text.contains("dhcpd_lease_expiry_seconds_bucket{pool=\"192.168.0.0/24\",le=\"1800\"} 1\n"));
+    assert!(text.contains("dhcpd_lease_expiry_seconds_bucket{pool=\"192.168.0.0/24\",le=\"+Inf\"} 2\n"));
+    assert!(text.contains("dhcpd_lease_expiry_seconds_sum{pool=\"192.168.0.0/24\"} 5400\n"));
+    assert!(text.contains("dhcpd_lease_expiry_seconds_count{pool=\"192.168.0.0/24\"} 2\n"));
+    assert!(!text.contains("dhcpd_lease_expiry_seconds_count{pool=\"192.168.1.0/24\"}"));
+
+    assert!(text.contains("dhcpd_lease_entries 5\n"));
+    assert!(text.contains("dhcpd_clients 3\n"));
+    assert!(text.contains("dhcpd_leases_parse_errors_total 2\n"));
+    assert!(text.contains("dhcpd_leases_file_age_seconds 90\n"));
+    assert!(text.ends_with("# EOF\n"));
+}
+
+#[test]
+fn metrics_by_pool_test() {
+    let leases = parser::parse(LEASES).unwrap().leases;
+    let text = MetricsBuilder::new()
+        .at(at())
+        .pool("clients", IpRange::from("192.168.0.2-192.168.0.3").unwrap())
+        .pool("empty", IpRange::from("10.0.0.0/8").unwrap())
+        .render(&leases);
+
+    assert!(text.contains("dhcpd_leases{pool=\"clients\",state=\"active\"} 2\n"));
+    assert!(text.contains("dhcpd_leases{pool=\"empty\",state=\"active\"} 0\n"));
+    assert!(text.contains("dhcpd_leases{pool=\"unassigned\",state=\"expired\"} 1\n"));
+    assert!(text.contains("dhcpd_leases{pool=\"unassigned\",state=\"abandoned\"} 1\n"));
+    assert!(!text.contains("dhcpd_leases_file_age_seconds"));
+
+    // The configured pools come first, in order
+    let clients = text.find("pool=\"clients\"").unwrap();
+    let empty = text.find("pool=\"empty\"").unwrap();
+    let unassigned = text.find("pool=\"unassigned\"").unwrap();
+    assert!(clients < empty && empty < unassigned);
+}
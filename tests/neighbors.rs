@@ -0,0 +1,94 @@
+extern crate dhcpd_parser;
+
+use std::net::IpAddr;
+
+use chrono::prelude::*;
+
+use crate::dhcpd_parser::common::IpRange;
+use crate::dhcpd_parser::neighbors::{self, Neighbor, NeighborCheck};
+use crate::dhcpd_parser::parser;
+
+#[test]
+fn parse_neighbors_test() {
+    let arp = neighbors::parse_file("tests/data/neighbors/arp").unwrap();
+    assert_eq!(arp.len(), 5);
+    assert_eq!(
+        arp[1],
+        Neighbor {
+            ip: "192.168.4.108".parse().unwrap(),
+            mac: Some("00:ad:d4:39:0d:04".to_owned()),
+            device: Some("eth0".to_owned()),
+            state: Some("0x2".to_owned()),
+        }
+    );
+    assert_eq!(arp[3].mac, None);
+
+    let neigh = neighbors::parse_file("tests/data/neighbors/neigh").unwrap();
+    assert_eq!(neigh.len(), 5);
+    assert_eq!(neigh[0].state.as_deref(), Some("REACHABLE"));
+    assert_eq!(neigh[2].mac, None);
+    assert_eq!(neigh[2].state.as_deref(), Some("FAILED"));
+    assert!(matches!(neigh[3].ip, IpAddr::V6(_)));
+    assert_eq!(neigh[4].mac.as_deref(), Some("00:11:22:33:44:05"));
+    assert_eq!(neigh[4].device.as_deref(), Some("eth1"));
+
+    assert_eq!(
+        neighbors::parse_arp("192.168.4.1 0x1 0x2 00:11:22:33:44:01 *").err(),
+        Some("line 1: Expected 6 columns, found '192.168.4.1 0x1 0x2 00:11:22:33:44:01 *'".to_owned()),
+    );
+    assert_eq!(
+        neighbors::parse_neigh("192.168.4.1 dev").err(),
+        Some("line 1: Expected a value after 'dev'".to_owned()),
+    );
+    assert_eq!(
+        neighbors::parse_neigh("gateway dev eth0").err(),
+        Some("line 1: 'gateway' is not a valid IP address".to_owned()),
+    );
+}
+
+#[test]
+fn parse_neighbors_non_utf8_test() {
+    let dir = std::env::temp_dir().join(format!("dhcpd-parser-neighbors-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("neigh");
+    std::fs::write(&path, b"192.168.4.1 dev caf\xe9 lladdr 00:11:22:33:44:01 REACHABLE\n").unwrap();
+
+    let neigh = neighbors::parse_file(&path).unwrap();
+    assert_eq!(neigh.len(), 1);
+    assert_eq!(neigh[0].device.as_deref(), Some("caf\\351"));
+}
+
+#[test]
+fn neighbor_check_test() {
+    let leases = parser::parse_file("tests/data/dhcpd-linux.leases").unwrap().leases;
+    // Only 192.168.4.108 is active
+    let at = Utc.with_ymd_and_hms(2022, 1, 8, 17, 50, 0).unwrap();
+
+    let arp = neighbors::parse_file("tests/data/neighbors/arp").unwrap();
+    let report = NeighborCheck::new().at(at).report(&arp, &leases);
+    let ips = |n: &[Neighbor]| n.iter().map(|n| n.ip.to_string()).collect::<Vec<String>>();
+    assert_eq!(ips(&report.unleased), ["192.168.4.1", "192.168.4.120", "10.0.0.5"]);
+    assert!(report.mismatches.is_empty());
+    assert!(report.absent.is_empty());
+
+    // Another device answers for the leased IP
+    let neigh = neighbors::parse_file("tests/data/neighbors/neigh").unwrap();
+    let report = NeighborCheck::new()
+        .at(at)
+        .pool(IpRange::from("192.168.4.100-192.168.4.200").unwrap())
+        .report(&neigh, &leases);
+    assert!(report.unleased.is_empty());
+    assert_eq!(report.mismatches.len(), 1);
+    assert_eq!(report.mismatches[0].neighbor.mac.as_deref(), Some("00:11:22:33:44:08"));
+    assert_eq!(report.mismatches[0].lease.ip, "192.168.4.108");
+
+    // The client of the active lease is not in the table
+    let report = NeighborCheck::new().at(at).report(&neigh[..1], &leases);
+    assert_eq!(report.absent.len(), 1);
+    assert_eq!(report.absent[0].ip, "192.168.4.108");
+
+    // Nothing is active later on
+    let report = NeighborCheck::new().at(at + chrono::Duration::hours(1)).report(&arp, &leases);
+    assert_eq!(report.unleased.len(), 4);
+    assert!(report.absent.is_empty());
+}
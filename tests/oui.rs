@@ -0,0 +1,58 @@
+#![cfg(feature = "oui")]
+extern crate dhcpd_parser;
+
+use dhcpd_parser::leases::{Hardware, LeasesMethods};
+use dhcpd_parser::oui::OuiDb;
+use dhcpd_parser::parser;
+use dhcpd_parser::util::LeaseFilterBuilder;
+
+#[test]
+fn oui_db_test() {
+    let db = OuiDb::from_files(&["tests/data/oui.csv"]).unwrap();
+    assert_eq!(db.len(), 9);
+
+    assert_eq!(db.vendor("b8:27:eb:01:02:03"), Some("Raspberry Pi Foundation"));
+    assert_eq!(db.vendor("F0-18-9E-01-02-03"), Some("Apple, Inc."));
+    assert_eq!(db.vendor("0:3:93:1:2:3"), Some("Apple, Inc."));
+    assert_eq!(db.vendor("00:e0:4c:68:dc:cf"), Some("REALTEK SEMICONDUCTOR CORP."));
+    assert_eq!(db.vendor("00:11:22:33:44:55"), None);
+    assert_eq!(db.vendor("not a mac"), None);
+
+    // The longest matching prefix: MA-S within MA-M within MA-L
+    assert_eq!(db.vendor("70:b3:d5:1a:21:00"), Some("Example Small Block"));
+    assert_eq!(db.vendor("70:b3:d5:1b:21:00"), Some("Example \"Medium\" Block"));
+    assert_eq!(db.vendor("70:b3:d5:2b:21:00"), Some("IEEE Registration Authority"));
+
+    let hardware = Hardware { h_type: "ethernet".to_owned(), mac: "dc:a6:32:01:02:03".to_owned() };
+    assert_eq!(hardware.vendor(&db), Some("Raspberry Pi Trading Ltd"));
+
+    assert_eq!(
+        OuiDb::from("MA-L,B827EX,Bad Prefix,Nowhere").err(),
+        Some("line 1: 'B827EX' is not a hexadecimal prefix".to_owned()),
+    );
+    assert!(OuiDb::from_files(&["tests/data/missing.csv"]).is_err());
+
+    // An organization name that is not UTF-8 (i.e., Latin-1)
+    let dir = std::env::temp_dir().join(format!("dhcpd-parser-oui-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("oui.csv");
+    std::fs::write(&path, b"MA-L,001122,Soci\xe9t\xe9 Example,Paris FR\n").unwrap();
+    let db = OuiDb::from_files(&[path]).unwrap();
+    assert_eq!(db.vendor("00:11:22:33:44:55"), Some("Soci\u{fffd}t\u{fffd} Example"));
+}
+
+#[test]
+fn filter_on_vendor_test() {
+    let db = OuiDb::from_files(&["tests/data/oui.csv"]).unwrap();
+    let leases = parser::parse_file("tests/data/dhcpd-multiple.leases").unwrap().leases;
+
+    let realtek = LeaseFilterBuilder::new(&leases).on_vendor(&db, "realtek").collect();
+    assert!(realtek.count() > 0);
+    assert_eq!(realtek, LeaseFilterBuilder::new(&leases).on_mac("00:e0:4c").collect());
+
+    let example = LeaseFilterBuilder::new(&leases).effective().on_vendor(&db, "Example Networks").collect();
+    assert!(example.count() > 0);
+    assert!(example.all().iter().all(|l| l.client().starts_with("00:ea:d4")));
+
+    assert_eq!(LeaseFilterBuilder::new(&leases).on_vendor(&db, "Apple").collect().count(), 0);
+}
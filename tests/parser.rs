@@ -0,0 +1,374 @@
+extern crate dhcpd_parser;
+
+use std::borrow::Cow;
+use std::fs::File;
+use std::io::{BufReader, Cursor};
+use std::path::PathBuf;
+use chrono::Utc;
+
+use crate::dhcpd_parser::common::Date;
+use crate::dhcpd_parser::parser;
+use crate::dhcpd_parser::parser::{Dialect, LeaseReader, LeasesMethods, ParseOptions};
+
+#[test]
+fn lease_reader_test() {
+    let reader = LeaseReader::new(Cursor::new(
+        "
+    # comment
+    lease 192.168.0.2 {
+        starts 2 2019/01/01 22:00:00 UTC;
+        ends 2 2019/01/01 23:00:00 UTC;
+        hardware type 11:11:11:11:11:11;
+        uid Client1;
+        client-hostname \"CLIENTHOSTNAME\";
+        hostname \"TESTHOSTNAME\";
+        abandoned;
+    }
+    lease 192.168.0.3 { starts 1 1985/01/01 00:00:00 UTC; hostname \"TESTHOSTNAME\"; }
+    ",
+    ));
+
+    let leases: Vec<_> = reader.collect::<Result<_, _>>().unwrap();
+    assert_eq!(leases.len(), 2);
+    assert_eq!(leases[0].ip, "192.168.0.2");
+    assert!(leases[0].abandoned);
+    assert_eq!(leases[1].ip, "192.168.0.3");
+    assert_eq!(leases[1].hostname.as_ref().unwrap(), "TESTHOSTNAME");
+}
+
+#[test]
+fn lease_reader_matches_parse_test() {
+    for filename in &["tests/data/dhcpd-linux.leases", "tests/data/dhcpd-multiple.leases", "tests/data/dhcpd-bsd.leases"] {
+        let content = std::fs::read_to_string(filename).unwrap();
+        let expected = parser::parse(content).unwrap().leases;
+
+        let reader = LeaseReader::new(BufReader::new(File::open(filename).unwrap()));
+        let leases: Vec<_> = reader.collect::<Result<_, _>>().unwrap();
+
+        assert_eq!(leases, expected.all(), "Mismatch for {}", filename);
+    }
+}
+
+#[test]
+fn lease_reader_error_test() {
+    let mut reader = LeaseReader::new(Cursor::new(
+        "lease 192.0.0.2 {
+        starts 2 2019/01/01 22:00:00 UTC;
+    }
+    lease 192.0.0.3 {
+        starts 2 2019/01/01 22:00:00 UTC;
+    ",
+    ));
+
+    assert!(reader.next().unwrap().is_ok());
+
+    let err = reader.next().unwrap().unwrap_err();
+    assert_eq!(err.line, 6);
+    assert!(reader.next().is_none());
+
+    // A truncated statement is an error
+    let mut reader = LeaseReader::new(Cursor::new("lease 10.0.0.1;
+"));
+    assert_eq!(reader.next().unwrap().unwrap_err().line, 1);
+    assert!(reader.next().is_none());
+}
+
+#[test]
+fn lease_reader_non_utf8_test() {
+    let content: &[u8] = b"lease 192.168.0.2 {\n  client-hostname \"caf\xe9\";\n}\n";
+    let leases: Vec<_> = LeaseReader::new(Cursor::new(content)).collect::<Result<_, _>>().unwrap();
+
+    assert_eq!(leases.len(), 1);
+    assert_eq!(leases[0].client_hostname.as_deref(), Some("caf\\351"));
+}
+
+#[test]
+fn parse_ref_matches_parse_test() {
+    for filename in &["tests/data/dhcpd-linux.leases", "tests/data/dhcpd-multiple.leases", "tests/data/dhcpd-bsd.leases"] {
+        let content = std::fs::read_to_string(filename).unwrap();
+        let expected = parser::parse(content.as_str()).unwrap().leases;
+
+        let leases: Vec<_> = parser::parse_ref(&content)
+            .unwrap()
+            .iter()
+            .map(|l| l.to_lease())
+            .collect();
+
+        assert_eq!(leases, expected.all(), "Mismatch for {}", filename);
+    }
+}
+
+#[test]
+fn parse_invalid_date_test() {
+    let input = "lease 192.0.0.2 {
+        ends 2 2019/01/01 xx:yy:zz;
+    }";
+
+    assert!(parser::parse(input).is_err());
+    assert!(parser::parse_ref(input).is_err());
+    assert!(LeaseReader::new(Cursor::new(input)).next().unwrap().is_err());
+}
+
+#[test]
+fn parse_truncated_test() {
+    let inputs = [
+        "lease 1.2.3.4 { ends epoch",
+        "lease 1.2.3.4 { ends epoch 1546380000",
+        "lease 1.2.3.4 { ends 2 2019/01/01",
+        "lease 1.2.3.4 { starts",
+        "lease 1.2.3.4 { hardware ethernet",
+        "lease 1.2.3.4 { uid",
+        "lease 1.2.3.4 { client-hostname",
+        "lease 1.2.3.4 { hostname",
+        "lease 1.2.3.4 { binding",
+        "lease 1.2.3.4 { next binding",
+        "lease 1.2.3.4 { abandoned",
+    ];
+
+    for &input in inputs.iter() {
+        assert!(parser::parse(input).is_err(), "{}", input);
+        assert!(parser::parse_ref(input).is_err(), "{}", input);
+    }
+}
+
+#[test]
+fn parse_ref_borrows_test() {
+    let content = "lease 192.168.0.2 {
+        hardware ethernet 11:11:11:11:11:11;
+        client-hostname \"CLIENTHOSTNAME\";
+    }";
+
+    let leases = parser::parse_ref(content).unwrap();
+    assert_eq!(leases.len(), 1);
+    assert_eq!(leases[0].hardware.as_ref().unwrap().mac, "11:11:11:11:11:11");
+    assert!(matches!(leases[0].client_hostname, Some(Cow::Borrowed("CLIENTHOSTNAME"))));
+
+    assert!(parser::parse_ref("lease 192.0.0.2 { starts 2 2019/01/01 22:00:00 UTC;").is_err());
+}
+
+#[test]
+fn parse_quoted_spaces_test() {
+    let content = "lease 192.168.0.2 {
+        client-hostname \"Bob's Laptop; 2nd\";
+    }";
+
+    let leases = parser::parse(content).unwrap().leases;
+    assert_eq!(leases[0].client_hostname.as_deref(), Some("Bob's Laptop; 2nd"));
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn parse_parallel_test() {
+    let mut content = std::fs::read_to_string("tests/data/dhcpd-multiple.leases").unwrap();
+    // Enough leases to be split into several chunks
+    for i in 0..5000 {
+        // Applies to the following leases, in the following chunks too
+        if i == 1000 {
+            content.push_str("authoring-byte-order big-endian;\n");
+        }
+        content.push_str(&format!(
+            "lease 10.0.{}.{} {{\n  starts 2 2021/11/30 19:57:47;\n  ends 2 2021/11/30 21:57:47;\n  \
+             binding state free;\n  hardware ethernet 00:ab:d4:41:21:{:02x};\n}}\n",
+            i / 256, i % 256, i % 256,
+        ));
+    }
+
+    let expected = parser::parse(content.as_str()).unwrap();
+    let res = parser::parse_parallel(&content).unwrap();
+
+    assert_eq!(res.leases.count(), 5016);
+    assert_eq!(res, expected);
+    assert_eq!(res.leases[1015].byte_order, Some("little-endian".to_string()));
+    assert_eq!(res.leases[5015].byte_order, Some("big-endian".to_string()));
+}
+
+/// Write `content` into a new file named `name` in a temporary directory for the test.
+fn write_temp_file(test: &str, name: &str, content: &[u8]) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("dhcpd-parser-{}-{}", test, std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let path = dir.join(name);
+    std::fs::write(&path, content).unwrap();
+    path
+}
+
+#[test]
+fn parse_file_test() {
+    let res = parser::parse_file("tests/data/dhcpd-linux.leases").unwrap();
+
+    assert_eq!(res.leases.count(), 6);
+    assert_eq!(res.source, Some(PathBuf::from("tests/data/dhcpd-linux.leases")));
+
+    assert!(parser::parse_file("tests/data/missing.leases").is_err());
+}
+
+#[test]
+fn parse_file_backup_test() {
+    let backup = write_temp_file("backup", "dhcpd.leases~", b"lease 192.168.0.2 { abandoned; }");
+    let path = backup.with_file_name("dhcpd.leases");
+
+    let res = parser::parse_file(&path).unwrap();
+    assert_eq!(res.leases.count(), 1);
+    assert_eq!(res.source, Some(backup));
+}
+
+#[test]
+fn parse_file_non_utf8_test() {
+    let path = write_temp_file(
+        "non-utf8",
+        "dhcpd.leases",
+        b"lease 192.168.0.2 {\n  client-hostname \"caf\xe9\";\n}\n",
+    );
+
+    let res = parser::parse_file(&path).unwrap();
+    assert_eq!(res.leases[0].client_hostname.as_ref().unwrap(), "caf\\351");
+}
+
+#[cfg(feature = "gzip")]
+#[test]
+fn parse_file_gzip_test() {
+    use std::io::Write;
+
+    let content = std::fs::read("tests/data/dhcpd-linux.leases").unwrap();
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(&content).unwrap();
+    let path = write_temp_file("gzip", "dhcpd.leases.gz", &encoder.finish().unwrap());
+
+    assert_eq!(parser::parse_file(&path).unwrap().leases.count(), 6);
+}
+
+#[cfg(feature = "zstd")]
+#[test]
+fn parse_file_zstd_test() {
+    let content = std::fs::read("tests/data/dhcpd-linux.leases").unwrap();
+    let path = write_temp_file("zstd", "dhcpd.leases.zst", &zstd::encode_all(content.as_slice(), 0).unwrap());
+
+    assert_eq!(parser::parse_file(&path).unwrap().leases.count(), 6);
+}
+
+#[cfg(feature = "xz")]
+#[test]
+fn parse_file_xz_test() {
+    use std::io::Write;
+
+    let content = std::fs::read("tests/data/dhcpd-linux.leases").unwrap();
+    let mut encoder = xz2::write::XzEncoder::new(Vec::new(), 6);
+    encoder.write_all(&content).unwrap();
+    let path = write_temp_file("xz", "dhcpd.leases.xz", &encoder.finish().unwrap());
+
+    assert_eq!(parser::parse_file(&path).unwrap().leases.count(), 6);
+}
+
+#[test]
+fn detect_format_test() {
+    let read = |path| std::fs::read_to_string(path).unwrap();
+
+    assert_eq!(parser::detect_format(&read("tests/data/dhcpd-bsd.leases")), Dialect::OpenBsd);
+    assert_eq!(parser::detect_format(&read("tests/data/dhcpd-linux.leases")), Dialect::Isc);
+    assert_eq!(parser::detect_format(&read("tests/data/dhcpd-multiple.leases")), Dialect::Isc);
+    assert_eq!(parser::detect_format(&read("tests/data/dhcpd6.leases")), Dialect::IscV6);
+    assert_eq!(parser::detect_format(""), Dialect::Isc);
+}
+
+#[test]
+fn parse_any_test() {
+    let read = |path| std::fs::read_to_string(path).unwrap();
+
+    let (dialect, res) = parser::parse_any(&read("tests/data/dhcpd-bsd.leases"), true).unwrap();
+    assert_eq!(dialect, Dialect::OpenBsd);
+    assert_eq!(res.leases.count(), 3);
+
+    let (dialect, res) = parser::parse_any(&read("tests/data/dhcpd-linux.leases"), true).unwrap();
+    assert_eq!(dialect, Dialect::Isc);
+    assert_eq!(res.leases, parser::parse_file("tests/data/dhcpd-linux.leases").unwrap().leases);
+
+    let (dialect, res) = parser::parse_any(&read("tests/data/dhcpd6.leases"), true).unwrap();
+    assert_eq!(dialect, Dialect::IscV6);
+    assert_eq!(res.leases.count(), 2);
+
+    let lease = &res.leases[0];
+    assert_eq!(lease.ip, "2001:db8:0:1::101");
+    assert_eq!(lease.uid.as_deref(), Some("\"\\016\\000\\374\\000\\000\\001\\000\\001*\\0104\\332RT\\000\\253\\315\\357\""));
+    assert_eq!(lease.binding_state.as_deref(), Some("active"));
+    assert_eq!(lease.byte_order.as_deref(), Some("little-endian"));
+    assert_eq!(lease.dates.cltt.unwrap().to_rfc3339(), "2021-11-30T19:57:47Z");
+    assert_eq!(lease.dates.ends.unwrap().to_rfc3339(), "2021-11-30T20:07:47Z");
+    assert_eq!(res.leases[1].ip, "2001:db8:100::/56");
+    assert_eq!(res.leases[1].binding_state.as_deref(), Some("expired"));
+
+    // Statements of the other dialect
+    let bsd = "lease 192.168.0.2 {\n  starts 2 2019/01/01 22:00:00 UTC;\n  ends 2 2019/01/01 23:00:00 UTC;\n  binding state active;\n}";
+    assert_eq!(
+        parser::parse_any(bsd, true).err(),
+        Some("line 4: 'binding' is not valid in 'lease' of the OpenBSD dialect".to_owned()),
+    );
+    assert_eq!(parser::parse_any(bsd, false).unwrap().1.leases[0].binding_state.as_deref(), Some("active"));
+
+    let isc = "authoring-byte-order little-endian;\nlease 192.168.0.2 {\n  on expiry { set x = 1; }\n  abandoned;\n}";
+    assert_eq!(
+        parser::parse_any(isc, true).err(),
+        Some("line 4: 'abandoned' is not valid in 'lease' of the ISC dialect".to_owned()),
+    );
+
+    let v6 = "server-duid \"\\000\";\nlease 192.168.0.2 { }";
+    assert_eq!(
+        parser::parse_any(v6, true).err(),
+        Some("line 2: 'lease' is not valid at the top level of the ISC DHCPv6 dialect".to_owned()),
+    );
+}
+
+#[test]
+fn parse_with_test() {
+    let read = |path| std::fs::read_to_string(path).unwrap();
+    let options = |dialect| ParseOptions { dialect, strict: true };
+
+    // The samples follow the grammar of their dialect, and only theirs
+    let bsd = read("tests/data/dhcpd-bsd.leases");
+    assert_eq!(parser::parse_with(&bsd, &options(Dialect::OpenBsd)).unwrap().leases.count(), 3);
+    assert_eq!(
+        parser::parse_with(&bsd, &options(Dialect::Isc)).err(),
+        Some("line 3: 'starts' expects 'weekday yyyy/mm/dd hh:mm:ss', found '2 2019/01/01 22:00:00 UTC'".to_owned()),
+    );
+    assert_eq!(parser::parse_with(&bsd, &ParseOptions::default()).unwrap().leases.count(), 3);
+
+    for path in ["tests/data/dhcpd-linux.leases", "tests/data/dhcpd-multiple.leases"] {
+        let isc = read(path);
+        assert!(parser::parse_with(&isc, &options(Dialect::Isc)).is_ok());
+        assert_eq!(
+            parser::parse_with(&isc, &options(Dialect::OpenBsd)).err(),
+            Some("line 5: 'authoring-byte-order' is not valid at the top level of the OpenBSD dialect".to_owned()),
+        );
+    }
+
+    let v6 = read("tests/data/dhcpd6.leases");
+    assert_eq!(parser::parse_with(&v6, &options(Dialect::IscV6)).unwrap().leases.count(), 2);
+    assert_eq!(
+        parser::parse_with(&v6, &options(Dialect::Isc)).err(),
+        Some("line 9: 'ia-na' is not valid at the top level of the ISC dialect".to_owned()),
+    );
+
+    let errors = [
+        (Dialect::Isc, "lease 192.168.0.2 { binding state bound; }", "line 1: 'binding' expects 'state free|active|expired|released|abandoned|reset|backup|bootp|reserved', found 'state bound'"),
+        (Dialect::Isc, "lease 192.168.0.2 {\n  ends 2 2019/13/01 23:00:00;\n}", "line 2: 'ends' expects 'weekday yyyy/mm/dd hh:mm:ss', found '2 2019/13/01 23:00:00'"),
+        (Dialect::Isc, "lease 192.168.0.2 { hardware ethernet 00:11:22:33:44:zz; }", "line 1: 'hardware' expects 'hardware-type mac-address', found 'ethernet 00:11:22:33:44:zz'"),
+        (Dialect::Isc, "authoring-byte-order middle-endian;", "line 1: 'authoring-byte-order' expects 'little-endian|big-endian', found 'middle-endian'"),
+        (Dialect::Isc, "lease 2001:db8::2 { }", "line 1: 'lease' expects 'ipv4-address', found '2001:db8::2'"),
+        (Dialect::Isc, "lease 192.168.0.2;", "line 1: 'lease' expects a block"),
+        (Dialect::Isc, "lease 192.168.0.2 { cltt 2 2019/01/01 22:00:00 }", "line 1: Expected semicolon after 'cltt 2 2019/01/01 22:00:00'"),
+        (Dialect::OpenBsd, "lease 192.168.0.2 { abandoned now; }", "line 1: 'abandoned' expects '', found 'now'"),
+        (Dialect::OpenBsd, "lease 192.168.0.2 { tstp 2 2019/01/01 22:00:00 UTC; }", "line 1: 'tstp' is not valid in 'lease' of the OpenBSD dialect"),
+        (Dialect::IscV6, "ia-na \"x\" { iaaddr 192.168.0.2 { } }", "line 1: 'iaaddr' expects 'ipv6-address', found '192.168.0.2'"),
+        (Dialect::IscV6, "ia-pd \"x\" { iaprefix 2001:db8::/129 { } }", "line 1: 'iaprefix' expects 'ipv6-address/length', found '2001:db8::/129'"),
+    ];
+    for (dialect, input, error) in errors.iter() {
+        assert_eq!(parser::parse_with(input, &options(*dialect)).err().as_deref(), Some(*error), "{}", input);
+    }
+
+    // The ISC dates can be in epoch seconds, and the "set" statements are free-form
+    let isc = "lease 192.168.0.2 {\n  starts epoch 1546380000; # Tue Jan 01 22:00:00 2019\n  ends never;\n  set ddns-fwd-name = \"host.example.org\";\n}";
+    let lease = &parser::parse_with(isc, &options(Dialect::Isc)).unwrap().leases[0];
+    assert_eq!(lease.dates.starts.unwrap().to_rfc3339(), "2019-01-01T22:00:00Z");
+    assert_eq!(lease.dates.ends, Some(Date::NEVER));
+    assert!(lease.is_bound_at(Utc::now()));
+    assert_eq!(parser::parse_ref(isc).unwrap()[0].to_lease(), *lease);
+}
@@ -0,0 +1,101 @@
+#![cfg(feature = "redact")]
+extern crate dhcpd_parser;
+
+use dhcpd_parser::export::write_leases;
+use dhcpd_parser::formats::kea::client_id;
+use dhcpd_parser::leases::LeasesMethods;
+use dhcpd_parser::parser::{self, Dialect, ParseOptions};
+use dhcpd_parser::redact::Redactor;
+
+#[test]
+fn redact_test() {
+    let leases = parser::parse_file("tests/data/dhcpd-multiple.leases").unwrap().leases;
+    let redacted = Redactor::new(b"key").redact(&leases);
+    assert_eq!(redacted.count(), leases.count());
+
+    for ndx in 0..leases.count() {
+        let (lease, pseudo) = (&leases[ndx], &redacted[ndx]);
+        assert_eq!(pseudo.ip, lease.ip);
+        assert_eq!(pseudo.dates, lease.dates);
+        assert_eq!(pseudo.binding_state, lease.binding_state);
+
+        let (mac, fake) = (&lease.hardware.as_ref().unwrap().mac, &pseudo.hardware.as_ref().unwrap().mac);
+        assert_ne!(fake, mac);
+        assert_eq!(fake.len(), mac.len());
+        // Locally administered unicast address
+        assert_eq!(u8::from_str_radix(&fake[..2], 16).unwrap() & 0x03, 0x02);
+
+        // The uid of the client holds the pseudonym of its MAC address
+        if lease.uid.is_some() {
+            assert_eq!(pseudo.uid.as_deref(), Some(format!("01:{}", fake).as_str()));
+        }
+        if let Some(hostname) = &pseudo.client_hostname {
+            assert!(hostname.starts_with("host-") && hostname.len() == 13);
+            assert!(!hostname.contains("clsomimx6"));
+        }
+    }
+
+    // The same value gets the same pseudonym, in every file redacted with the same key
+    let linux = Redactor::new(b"key").redact(&parser::parse_file("tests/data/dhcpd-linux.leases").unwrap().leases);
+    assert_eq!(linux[1].hardware, redacted[1].hardware);
+    assert_eq!(linux[1].uid, redacted[1].uid);
+    assert_ne!(Redactor::new(b"other key").redact(&leases)[0].hardware, redacted[0].hardware);
+
+    // The redacted file can be loaded again, and follows the grammar of ISC dhcpd
+    let mut out = Vec::new();
+    write_leases(&redacted, Dialect::Isc, &mut out).unwrap();
+    let options = ParseOptions { dialect: Dialect::Isc, strict: true };
+    assert_eq!(parser::parse_with(&String::from_utf8(out).unwrap(), &options).unwrap().leases, redacted);
+}
+
+#[test]
+fn redact_options_test() {
+    let mut redactor = Redactor::new(b"key");
+    redactor.keep_oui(true).ips(true);
+
+    assert!(redactor.mac("00:AB:D4:41:21:10").starts_with("00:ab:d4:"));
+    assert_eq!(redactor.mac("00:AB:D4:41:21:10"), redactor.mac("00:ab:d4:41:21:10"));
+    assert_eq!(redactor.uid("\"\\001\\000\\253\\324A!\\020\""), format!("01:{}", redactor.mac("00:ab:d4:41:21:10")));
+    assert_eq!(redactor.uid("Client1").len(), "43:6c:69:65:6e:74:31".len());
+
+    // The IPs are shuffled within their family, without collisions
+    let ips: std::collections::HashSet<String> = (0..=255).map(|n| redactor.ip(&format!("192.168.0.{}", n))).collect();
+    assert_eq!(ips.len(), 256);
+    assert!(ips.iter().all(|ip| ip.parse::<std::net::Ipv4Addr>().is_ok()));
+    assert!(redactor.ip("2001:db8::1").parse::<std::net::Ipv6Addr>().is_ok());
+    let prefix = redactor.ip("2001:db8:100::/56");
+    assert!(prefix.ends_with("/56"));
+    assert_eq!(prefix.split('/').next().unwrap().parse::<std::net::Ipv6Addr>().unwrap().segments()[3] & 0xff, 0);
+
+    // Only the bits of the prefix are shuffled: distinct prefixes, of any length, keep distinct
+    // pseudonyms
+    for len in [56u32, 9, 1] {
+        let count = 1u128 << len.min(9);
+        let prefixes: std::collections::HashSet<String> = (0..count)
+            .map(|n| redactor.ip(&format!("{}/{}", std::net::Ipv6Addr::from(n << (128 - len)), len)))
+            .collect();
+        assert_eq!(prefixes.len() as u128, count, "/{}", len);
+        assert!(prefixes.iter().all(|p| p.ends_with(&format!("/{}", len))));
+    }
+    assert_eq!(redactor.ip("2001:db8::/0"), "::/0");
+    assert_eq!(redactor.ip("localhost"), "localhost");
+
+    // The DHCPv6 leases, with their IA and prefix
+    let options = ParseOptions { dialect: Dialect::IscV6, strict: true };
+    let leases = parser::parse_with(&std::fs::read_to_string("tests/data/dhcpd6.leases").unwrap(), &options).unwrap().leases;
+    let redacted = redactor.redact(&leases);
+    assert_ne!(redacted[0].ip, leases[0].ip);
+    assert_ne!(redacted[0].uid, leases[0].uid);
+
+    // The IA is written as a quoted string, as dhcpd reads it
+    let mut out = Vec::new();
+    write_leases(&redacted, Dialect::IscV6, &mut out).unwrap();
+    let out = String::from_utf8(out).unwrap();
+    assert!(out.lines().filter(|l| l.starts_with("ia-")).all(|l| l.split(' ').nth(1).unwrap().starts_with('"')));
+    let back = parser::parse_with(&out, &options).unwrap().leases;
+    assert_eq!(back.count(), redacted.count());
+    for ndx in 0..back.count() {
+        assert_eq!(back[ndx].ip, redacted[ndx].ip);
+        assert_eq!(back[ndx].uid.as_deref().map(client_id), redacted[ndx].uid.clone());
+    }
+}
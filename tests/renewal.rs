@@ -0,0 +1,65 @@
+extern crate dhcpd_parser;
+
+use chrono::prelude::*;
+
+use crate::dhcpd_parser::common::IpRange;
+use crate::dhcpd_parser::parser;
+use crate::dhcpd_parser::renewal::{Distribution, RenewalAnalyzer};
+
+#[test]
+fn distribution_test() {
+    assert_eq!(Distribution::of(&[]), None);
+
+    let d = Distribution::of(&[4.0, 1.0, 3.0, 2.0, 10.0, 5.0, 6.0, 7.0, 8.0, 9.0]).unwrap();
+    assert_eq!((d.count, d.min, d.max), (10, 1.0, 10.0));
+    assert_eq!((d.mean, d.median, d.p90), (5.5, 5.5, 9.0));
+
+    let d = Distribution::of(&[600.0]).unwrap();
+    assert_eq!((d.median, d.p90), (600.0, 600.0));
+}
+
+#[test]
+fn renewal_stats_test() {
+    let leases = parser::parse_file("tests/data/dhcpd-multiple.leases").unwrap().leases;
+    let at = Utc.with_ymd_and_hms(2022, 1, 12, 0, 0, 0).unwrap();
+    let stats = RenewalAnalyzer::new()
+        .pool("low", &[IpRange::from("192.168.4.100-192.168.4.106").unwrap()])
+        .pool("high", &[IpRange::from("192.168.4.107-192.168.4.120").unwrap()])
+        .at(at)
+        .report(&leases);
+
+    let clients: Vec<&str> = stats.clients.iter().map(|c| c.client.as_str()).collect();
+    assert_eq!(clients, ["00:ea:d4:41:21:10", "00:e0:4c:68:dc:cf", "00:ea:d4:39:0d:04", "00:aa:bb:cc:dd:01"]);
+
+    // Renews its 10 minute lease every 4 to 5 minutes
+    let renewing = &stats.clients[2].stats;
+    assert_eq!((renewing.grants, renewing.renewals), (12, 8));
+    let interval = renewing.renewal_interval.unwrap();
+    assert_eq!((interval.min, interval.max), (229.0, 299.0));
+    let elapsed = renewing.elapsed_at_renewal.unwrap();
+    assert!(elapsed.min > 0.35 && elapsed.max < 0.5);
+    assert_eq!(renewing.duration.unwrap().max, 7200.0);
+
+    // Renews once, the duplicated entries of the journal are skipped
+    let once = &stats.clients[3].stats;
+    assert_eq!((once.grants, once.renewals), (2, 1));
+    assert_eq!(once.renewal_interval.unwrap().median, 280.0);
+
+    assert_eq!(stats.never_renewed, ["00:ea:d4:41:21:10", "00:e0:4c:68:dc:cf"]);
+    assert_eq!(stats.overall.grants, 16);
+    assert_eq!(stats.overall.renewals, 9);
+    assert_eq!(stats.overall.duration.unwrap().count, 16);
+
+    assert_eq!(stats.pools[0].name, "low");
+    assert_eq!((stats.pools[0].stats.grants, stats.pools[0].stats.renewals), (6, 1));
+    assert_eq!((stats.pools[1].stats.grants, stats.pools[1].stats.renewals), (10, 8));
+
+    // The lease of the second client has not ended yet, nor the lease of the first one before
+    let at = Utc.with_ymd_and_hms(2021, 12, 1, 0, 0, 0).unwrap();
+    let stats = RenewalAnalyzer::new().at(at).report(&leases);
+    assert_eq!(stats.never_renewed, ["00:ea:d4:41:21:10"]);
+    assert!(stats.pools.is_empty());
+
+    let at = Utc.with_ymd_and_hms(2021, 11, 30, 21, 0, 0).unwrap();
+    assert!(RenewalAnalyzer::new().at(at).report(&leases).never_renewed.is_empty());
+}
@@ -0,0 +1,78 @@
+extern crate dhcpd_parser;
+
+use crate::dhcpd_parser::common::Date;
+use crate::dhcpd_parser::leases::{Lease, LeasesMethods};
+use crate::dhcpd_parser::parser;
+use crate::dhcpd_parser::store::LeaseStore;
+use crate::dhcpd_parser::util::LeaseFilterBuilder;
+
+#[test]
+fn lease_store_test() {
+    let leases = parser::parse_file("tests/data/dhcpd-multiple.leases").unwrap().leases;
+    let store = LeaseStore::from_leases(&leases);
+
+    // The same leases as the effective filter
+    let effective = LeaseFilterBuilder::new(&leases).effective().collect().all();
+    assert_eq!(store.len(), effective.len());
+    for lease in effective.iter() {
+        assert_eq!(store.by_ip(&lease.ip), Some(lease));
+    }
+    assert_eq!(store.by_ip("192.168.4.200"), None);
+
+    assert_eq!(store.by_mac("00:ea:d4:41:21:10").unwrap().ip, "192.168.4.101");
+    assert_eq!(store.by_mac("00:AA:BB:CC:DD:01").unwrap().ip, "192.168.4.106");
+    assert_eq!(store.by_mac("00:ea:d4:39:0d:04").unwrap().ip, "192.168.4.108");
+    let uid = store.by_ip("192.168.4.104").unwrap().uid.clone().unwrap();
+    assert_eq!(store.by_uid(&uid).unwrap().ip, "192.168.4.104");
+    assert_eq!(store.by_hostname("CLSOMIMX6").unwrap().ip, "192.168.4.108");
+    assert_eq!(store.by_hostname("unknown"), None);
+
+    let from = Date::from("2", "2022/01/11", "00:00:00").unwrap();
+    let to = Date::from("2", "2022/01/11", "00:13:17").unwrap();
+    let ending: Vec<&str> = store.ending_between(from..to).map(|l| l.ip.as_str()).collect();
+    assert_eq!(ending, vec!["192.168.4.106"]);
+    let ending: Vec<&str> = store.ending_between(from..=to).map(|l| l.ip.as_str()).collect();
+    assert_eq!(ending, vec!["192.168.4.106", "192.168.4.108"]);
+    assert_eq!(store.ending_between(..from).count(), store.len() - 2);
+
+    // In the order the IPs were first seen
+    let mut all = store.to_leases().all();
+    assert_eq!(all[1].ip, "192.168.4.104");
+    all.sort_by(|a, b| a.ip.cmp(&b.ip));
+    let mut effective = effective;
+    effective.sort_by(|a, b| a.ip.cmp(&b.ip));
+    assert_eq!(all, effective);
+}
+
+#[test]
+fn lease_store_push_test() {
+    let mut store = LeaseStore::new();
+    assert!(store.is_empty());
+
+    let mut first = Lease::new();
+    first.ip = "192.168.4.10".to_owned();
+    first.client_hostname = Some("laptop".to_owned());
+    first.dates.ends = Some(Date::from("2", "2022/01/11", "00:00:00").unwrap());
+    assert_eq!(store.push(first.clone()), None);
+
+    let mut second = first.clone();
+    second.client_hostname = Some("desktop".to_owned());
+    second.dates.ends = Some(Date::from("2", "2022/01/11", "01:00:00").unwrap());
+    assert_eq!(store.push(second.clone()), Some(first.clone()));
+
+    // The replaced lease is no longer indexed
+    assert_eq!(store.len(), 1);
+    assert_eq!(store.by_hostname("laptop"), None);
+    assert_eq!(store.by_hostname("desktop"), Some(&second));
+    let ends = first.dates.ends.unwrap();
+    assert_eq!(store.ending_between(ends..=ends).count(), 0);
+
+    // The last pushed lease of a hostname on several IPs
+    let mut other = second.clone();
+    other.ip = "192.168.4.11".to_owned();
+    store.push(other.clone());
+    assert_eq!(store.by_hostname("desktop"), Some(&other));
+    store.push(second.clone());
+    assert_eq!(store.by_hostname("desktop"), Some(&second));
+    assert_eq!(store.ending_between(..).count(), 2);
+}
@@ -171,3 +171,41 @@ fn filter_on_ip_expired_test() {
         Err(e) => assert!(false, "{}", e)
     }
 }
+
+#[test]
+fn filter_effective_test() {
+    let leases = parser::parse_file("tests/data/dhcpd-multiple.leases").unwrap().leases;
+    let mut builder = LeaseFilterBuilder::new(&leases);
+
+    let filtered = builder.effective().collect();
+    let ips: Vec<String> = filtered.all().iter().map(|l| l.ip.clone()).collect();
+    let unique: std::collections::HashSet<&String> = ips.iter().collect();
+
+    assert_eq!(ips.len(), unique.len());
+
+    let mut builder = LeaseFilterBuilder::new(&leases);
+    let filtered = builder.effective().on_ip("192.168.4.106").collect();
+    assert_eq!(filtered.count(), 1);
+    assert_eq!(filtered[0], leases.all().into_iter().rev().find(|l| l.ip == "192.168.4.106").unwrap());
+}
+
+#[test]
+fn filter_on_active_at_test() {
+    let leases = parser::parse_file("tests/data/dhcpd-multiple.leases").unwrap().leases;
+
+    // After the 2nd from the end started, but before the last item
+    let compare_dt = Utc.with_ymd_and_hms(2022, 1, 11, 0, 7, 10).unwrap();
+    let mut builder = LeaseFilterBuilder::new(&leases);
+    let filtered = builder.on_mac("00:ea:d4:39:0d:04")
+        .on_active_at(Some(compare_dt))
+        .collect();
+
+    assert_eq!(filtered.count(), 2);
+    for ndx in 0..filtered.count() {
+        assert!(filtered[ndx].is_bound_at(compare_dt));
+        assert_eq!(filtered[ndx].binding_state.as_deref(), Some("active"));
+    }
+
+    let mut builder = LeaseFilterBuilder::new(&leases);
+    assert_eq!(builder.on_active_at(Some(Utc.with_ymd_and_hms(2000, 1, 1, 0, 0, 0).unwrap())).collect().count(), 0);
+}
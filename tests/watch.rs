@@ -0,0 +1,48 @@
+#![cfg(feature = "tokio")]
+extern crate dhcpd_parser;
+
+use std::path::PathBuf;
+use std::time::Duration;
+use dhcpd_parser::leases::LeasesMethods;
+use dhcpd_parser::parser;
+use dhcpd_parser::watch;
+use tokio_stream::StreamExt;
+
+const ONE_LEASE: &str = "lease 192.168.0.2 { abandoned; }\n";
+const TWO_LEASES: &str = "lease 192.168.0.2 { abandoned; }\nlease 192.168.0.3 { abandoned; }\n";
+
+fn temp_dir(test: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("dhcpd-parser-{}-{}", test, std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[tokio::test]
+async fn parse_file_async_test() {
+    let res = parser::parse_file_async("tests/data/dhcpd-linux.leases").await.unwrap();
+
+    assert_eq!(res.leases.count(), 6);
+}
+
+#[tokio::test]
+async fn watch_rename_test() {
+    let dir = temp_dir("watch");
+    let path = dir.join("dhcpd.leases");
+    std::fs::write(&path, ONE_LEASE).unwrap();
+
+    let mut snapshots = watch::watch_debounced(&path, Duration::from_millis(100)).unwrap();
+    let first = snapshots.next().await.unwrap().unwrap();
+    assert_eq!(first.count(), 1);
+
+    // Rewrite the file the way dhcpd does: write a new file and rename it over the old one
+    let new_path = dir.join("dhcpd.leases.new");
+    std::fs::write(&new_path, TWO_LEASES).unwrap();
+    std::fs::rename(&new_path, &path).unwrap();
+
+    let second = tokio::time::timeout(Duration::from_secs(10), snapshots.next())
+        .await
+        .expect("No snapshot after the file changed")
+        .unwrap()
+        .unwrap();
+    assert_eq!(second.count(), 2);
+}